@@ -1,28 +1,73 @@
-use std::str::FromStr;
-
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    from_binary, to_binary, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Reply, Response,
-    StdResult, SubMsg, Uint128, WasmMsg,
+    coin, from_json, instantiate2_address, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg,
+    Decimal, Deps, DepsMut, Empty, Env, HexBinary, MessageInfo, Order, Reply, Response, StdError,
+    StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
-use cw2::set_contract_version;
+use cw2::{set_contract_version, CONTRACT as CONTRACT_INFO};
+use cw_storage_plus::Bound;
 use cw_utils::parse_reply_instantiate_data;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
 
-use cosmoswap::msg::InstantiateMsg as CosmoswapInstantiateMsg;
-use cosmoswap_packages::funds::{check_single_coin, FundsError};
-use cosmoswap_packages::types::{FeeInfo, SwapInfo};
-use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, Expiration, TokenInfoResponse};
+use cosmoswap::msg::{
+    ExecuteMsg as CosmoswapExecuteMsg, InstantiateMsg as CosmoswapInstantiateMsg,
+    QueryMsg as CosmoswapQueryMsg, ReceiveMsg as CosmoswapReceiveMsg,
+};
+use cosmoswap::state::{
+    Config as CosmoswapConfig, Swap as CosmoswapSwap, SwapStatus as CosmoswapStatus,
+};
+use cosmoswap_packages::fees::{calculate_fee, fee_msg, validate_fee_bounds};
+use cosmoswap_packages::funds::{
+    check_cw20_allowance, check_single_coin, transfer_from_msg, transfer_msg, FundsError,
+};
+use cosmoswap_packages::types::{
+    FeeBound, FeeDestination, FeeInfo, PauseInfo, PriceDecay, Recipient, Rounding, SwapCoin,
+    SwapEvent, SwapInfo,
+};
+use cw20::{
+    BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, Expiration, TokenInfoResponse,
+};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg};
-use crate::state::{Config, CONFIG, FEE_CONFIG};
+use crate::msg::{
+    CodeIdResponse, CodeIdsResponse, DepositsResponse, EffectiveFeeResponse, ExecuteMsg,
+    HookGasLimitResponse, HooksResponse, InstantiateMsg, IsNonceUsedResponse,
+    IsRegisteredSwapResponse, Offer, PredictSwapAddressResponse, QueryMsg, ReceiveMsg,
+    RoleHoldersResponse, StakedBalanceAtHeightResponse, StakingQueryMsg, SwapHookMsg,
+    SweepBountyResponse,
+};
+use crate::state::{
+    CodeIdInfo, Config, CreationDeposit, CreationDepositConfig, DiscountSource, FeeDiscount,
+    FeeDiscountTier, PendingAdmin, PendingFeeConfig, PendingInstantiate, RegistryStatus, Role,
+    CODE_IDS, CONFIG, CREATION_DEPOSITS, CREATION_DEPOSIT_CONFIG, DEPOSITS, EXPIRING_AT_HEIGHT,
+    EXPIRING_AT_TIME, EXPIRING_SWAPS, FEE_CONFIG, FEE_DISCOUNT, HOOKS, HOOK_GAS_LIMIT, PAUSE_INFO,
+    PENDING_ADMIN, PENDING_FEE_CONFIG, PENDING_INSTANTIATE, ROLES, SWAPS, SWAP_COUNT, SWAP_IDS,
+    SWAP_NONCES, SWAP_STATUSES, SWEEP_BOUNTY, SWEEP_POOL, USED_NONCES,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cosmoswap-controller";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const INSTANTIATE_REPLY_ID: u64 = 1;
+const HOOK_REPLY_ID: u64 = 2;
+
+// Gas each hook subscriber can use unless the list curator sets another limit
+const DEFAULT_HOOK_GAS_LIMIT: u64 = 200_000;
+
+// cw2 contract name every whitelisted code id needs to report
+const COSMOSWAP_CONTRACT_NAME: &str = "crates.io:cosmoswap";
+
+// Pagination limits for list queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// Fee percentage can never be higher than this
+const MAX_FEE_PERCENT: u64 = 10;
+// Seconds to wait before a proposed fee config can be applied
+const FEE_CONFIG_TIMELOCK: u64 = 3 * 24 * 60 * 60;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -35,21 +80,45 @@ pub fn instantiate(
 
     // Save the code id cosmoswap contract instantiation
     let config = Config {
-        admin: info.sender,
+        admin: Some(info.sender.clone()),
         cosmoswap_code_id: msg.cosmoswap_code_id,
     };
     CONFIG.save(deps.storage, &config)?;
 
+    // Initial code id is whitelisted as the default one
+    CODE_IDS.save(
+        deps.storage,
+        msg.cosmoswap_code_id,
+        &CodeIdInfo {
+            version: msg.cosmoswap_version,
+            enabled: true,
+        },
+    )?;
+
     // Save the fee config for setting it on cosmoswap contract instantiation
-    let fee_config = FeeInfo {
-        percentage: msg.fee_percentage,
-        payment_address: deps.api.addr_validate(&msg.fee_payment_address)?,
-    };
+    let fee_config = build_fee_config(
+        deps.as_ref(),
+        msg.fee_percentage,
+        msg.fee_payment_address,
+        msg.fee_rounding,
+        msg.fee_bounds,
+        msg.fee_destination,
+    )?;
     FEE_CONFIG.save(deps.storage, &fee_config)?;
 
+    PAUSE_INFO.save(
+        deps.storage,
+        &PauseInfo {
+            creation: false,
+            settlement: false,
+        },
+    )?;
+
+    SWAP_COUNT.save(deps.storage, &0)?;
+
     Ok(Response::new()
         .add_attribute("action", "instantiate")
-        .add_attribute("admin", config.admin)
+        .add_attribute("admin", info.sender)
         .add_attribute("cosmoswap_code_id", config.cosmoswap_code_id.to_string())
         .add_attribute("fee_percentage", fee_config.percentage.to_string())
         .add_attribute("fee_percentage", fee_config.payment_address.to_string()))
@@ -66,249 +135,1848 @@ pub fn execute(
         ExecuteMsg::UpdateConfig { cosmoswap_code_id } => {
             execute_update_config(deps, env, info, cosmoswap_code_id)
         }
-        ExecuteMsg::UpdateFeeConfig {
+        ExecuteMsg::SetCodeId {
+            code_id,
+            version,
+            enabled,
+        } => execute_set_code_id(deps, env, info, code_id, version, enabled),
+        ExecuteMsg::RemoveCodeId { code_id } => execute_remove_code_id(deps, env, info, code_id),
+        ExecuteMsg::ProposeFeeConfig {
             fee_percentage,
             fee_payment_address,
-        } => execute_update_fee_config(deps, env, info, fee_percentage, fee_payment_address),
+            fee_rounding,
+            fee_bounds,
+            fee_destination,
+        } => execute_propose_fee_config(
+            deps,
+            env,
+            info,
+            fee_percentage,
+            fee_payment_address,
+            fee_rounding,
+            fee_bounds,
+            fee_destination,
+        ),
+        ExecuteMsg::ApplyFeeConfig {} => execute_apply_fee_config(deps, env, info),
+        ExecuteMsg::CancelFeeConfig {} => execute_cancel_fee_config(deps, env, info),
+        ExecuteMsg::UpdateFeeDiscount { source, tiers } => {
+            execute_update_fee_discount(deps, env, info, source, tiers)
+        }
+        ExecuteMsg::RemoveFeeDiscount {} => execute_remove_fee_discount(deps, env, info),
+        ExecuteMsg::SetPause {
+            creation,
+            settlement,
+        } => execute_set_pause(deps, env, info, creation, settlement),
+        ExecuteMsg::SetCreationDeposit { creation_deposit } => {
+            execute_set_creation_deposit(deps, env, info, creation_deposit)
+        }
+        ExecuteMsg::SetSweepBounty { bounty } => execute_set_sweep_bounty(deps, env, info, bounty),
+        ExecuteMsg::FundSweepPool {} => execute_fund_sweep_pool(deps, env, info),
+        ExecuteMsg::SweepExpired { limit } => execute_sweep_expired(deps, env, info, limit),
+        ExecuteMsg::AddHook { address } => execute_add_hook(deps, env, info, address),
+        ExecuteMsg::RemoveHook { address } => execute_remove_hook(deps, env, info, address),
+        ExecuteMsg::SetHookGasLimit { gas_limit } => {
+            execute_set_hook_gas_limit(deps, env, info, gas_limit)
+        }
+        ExecuteMsg::NotifySwap { event } => execute_notify_swap(deps, env, info, event),
+        ExecuteMsg::ProposeNewAdmin {
+            address,
+            expiration,
+        } => execute_propose_new_admin(deps, env, info, address, expiration),
+        ExecuteMsg::GrantRole { role, address } => {
+            execute_grant_role(deps, env, info, role, address)
+        }
+        ExecuteMsg::RevokeRole { role, address } => {
+            execute_revoke_role(deps, env, info, role, address)
+        }
+        ExecuteMsg::AcceptAdmin {} => execute_accept_admin(deps, env, info),
+        ExecuteMsg::RenounceAdmin {} => execute_renounce_admin(deps, env, info),
         ExecuteMsg::CreateSwap {
             swap_info,
             expiration,
+            code_id,
+            hash_lock,
         } => {
-            if info.sender.to_string() != swap_info.user1 {
+            if info.sender != swap_info.user1 {
                 return Err(ContractError::Unauthorized {});
             }
-            execute_create_swap(deps, env, info, swap_info, expiration)
+            // cw20 coins are pulled with the allowance of user1
+            if !swap_info.coin1.is_native {
+                validate_cw20_coin(deps.as_ref(), &swap_info.coin1)?;
+                check_cw20_allowance(
+                    &deps.querier,
+                    &env.block,
+                    &swap_info.coin1,
+                    &info.sender,
+                    &env.contract.address,
+                )?;
+            }
+            if !swap_info.coin2.is_native {
+                validate_cw20_coin(deps.as_ref(), &swap_info.coin2)?;
+            }
+            execute_create_swap(
+                deps, env, info, swap_info, expiration, code_id, hash_lock, false,
+            )
+        }
+        ExecuteMsg::Deposit {} => execute_deposit(deps, env, info),
+        ExecuteMsg::Withdraw { amount } => execute_withdraw(deps, env, info, amount),
+        ExecuteMsg::TakeOffer {
+            offer,
+            signature,
+            pubkey,
+        } => {
+            if !offer.swap_info.coin2.is_native {
+                return Err(ContractError::InvalidOffer {});
+            }
+            check_single_coin(&info, &offer.swap_info.coin2.coin)?;
+            execute_take_offer(deps, env, info.sender, offer, signature, pubkey)
         }
+        ExecuteMsg::CancelNonce { nonce } => execute_cancel_nonce(deps, env, info, nonce),
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
     }
 }
 
-fn execute_update_config(
+fn assert_admin(config: &Config, sender: &Addr) -> Result<(), ContractError> {
+    if config.admin.as_ref() != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Admin is allowed to do everything, other addresses need to be granted the role
+fn assert_role(
+    deps: Deps,
+    config: &Config,
+    sender: &Addr,
+    role: Role,
+) -> Result<(), ContractError> {
+    if config.admin.as_ref() == Some(sender) {
+        return Ok(());
+    }
+    if !ROLES.has(deps.storage, (role.as_str(), sender)) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+fn execute_grant_role(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    cosmoswap_code_id: u64,
+    role: Role,
+    address: String,
 ) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    let config = CONFIG.load(deps.storage)?;
+    assert_admin(&config, &info.sender)?;
 
-    config.cosmoswap_code_id = cosmoswap_code_id;
-    CONFIG.save(deps.storage, &config)?;
+    let address = deps.api.addr_validate(&address)?;
+    ROLES.save(deps.storage, (role.as_str(), &address), &Empty {})?;
 
     Ok(Response::new()
-        .add_attribute("action", "update_config")
-        .add_attribute("cosmoswap_code_id", config.cosmoswap_code_id.to_string()))
+        .add_attribute("action", "grant_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
 }
 
-fn execute_update_fee_config(
+fn execute_revoke_role(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    fee_percentage: Decimal,
-    fee_payment_address: String,
+    role: Role,
+    address: String,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    assert_admin(&config, &info.sender)?;
 
-    let fee_config = FeeInfo {
-        percentage: fee_percentage,
-        payment_address: deps.api.addr_validate(&fee_payment_address)?,
-    };
-    FEE_CONFIG.save(deps.storage, &fee_config)?;
+    let address = deps.api.addr_validate(&address)?;
+    if !ROLES.has(deps.storage, (role.as_str(), &address)) {
+        return Err(ContractError::RoleNotFound {});
+    }
+    ROLES.remove(deps.storage, (role.as_str(), &address));
 
     Ok(Response::new()
-        .add_attribute("action", "update_fee_config")
-        .add_attribute("fee_percentage", fee_config.percentage.to_string())
-        .add_attribute(
-            "fee_payment_address",
-            fee_config.payment_address.to_string(),
-        ))
+        .add_attribute("action", "revoke_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
 }
 
-fn execute_create_swap(
+fn execute_propose_new_admin(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    swap_info: SwapInfo,
+    address: String,
     expiration: Expiration,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let fee_config = FEE_CONFIG.load(deps.storage)?;
+    assert_admin(&config, &info.sender)?;
 
     if expiration.is_expired(&env.block) {
         return Err(ContractError::InvalidExpiration {});
     }
 
-    if swap_info.user1 == swap_info.user2 {
-        return Err(ContractError::SameUsers {});
+    let pending_admin = PendingAdmin {
+        address: deps.api.addr_validate(&address)?,
+        expiration,
     };
+    PENDING_ADMIN.save(deps.storage, &pending_admin)?;
 
-    if swap_info.coin1.coin.denom == swap_info.coin2.coin.denom {
-        return Err(ContractError::SameDenoms {});
-    };
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_admin")
+        .add_attribute("pending_admin", pending_admin.address)
+        .add_attribute("expiration", pending_admin.expiration.to_string()))
+}
 
-    if swap_info.coin1.is_native {
-        check_single_coin(&info, &swap_info.coin1.coin)?;
-    };
+fn execute_accept_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending_admin = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin {})?;
+    if info.sender != pending_admin.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    if pending_admin.expiration.is_expired(&env.block) {
+        return Err(ContractError::AdminProposalExpired {});
+    }
 
-    let wasm_msg = WasmMsg::Instantiate {
-        code_id: config.cosmoswap_code_id,
-        msg: to_binary(&CosmoswapInstantiateMsg {
-            fee_info: fee_config,
-            swap_info: swap_info.clone(),
-            expiration,
-        })?,
-        funds: info.funds.clone(),
-        admin: None,
-        label: "Cosmoswap Contract".to_string(),
-    };
+    let mut config = CONFIG.load(deps.storage)?;
+    config.admin = Some(pending_admin.address);
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_ADMIN.remove(deps.storage);
 
-    let msg = match swap_info.coin1.is_native {
-        true => {
-            check_single_coin(&info, &swap_info.coin1.coin)?;
-            SubMsg::new(wasm_msg)
-        }
-        false => SubMsg::reply_on_success(wasm_msg, INSTANTIATE_REPLY_ID),
-    };
+    Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("admin", info.sender))
+}
+
+fn execute_renounce_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_admin(&config, &info.sender)?;
+
+    config.admin = None;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "renounce_admin"))
+}
+
+fn execute_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    cosmoswap_code_id: u64,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::CodeManager)?;
+
+    // Default code id needs to be whitelisted
+    assert_code_id_enabled(deps.as_ref(), cosmoswap_code_id)?;
+
+    config.cosmoswap_code_id = cosmoswap_code_id;
+    CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new()
-        .add_submessage(msg)
-        .add_attribute("action", "create_swap"))
+        .add_attribute("action", "update_config")
+        .add_attribute("cosmoswap_code_id", config.cosmoswap_code_id.to_string()))
 }
 
-fn execute_receive(
+fn execute_set_code_id(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    cw20_recieve_msg: Cw20ReceiveMsg,
+    code_id: u64,
+    version: String,
+    enabled: bool,
 ) -> Result<Response, ContractError> {
-    let msg: ReceiveMsg = from_binary(&cw20_recieve_msg.msg)?;
-    match msg {
-        ReceiveMsg::CreateSwap {
-            swap_info,
-            expiration,
-        } => {
-            // Check if the sender is the same as the user1
-            if cw20_recieve_msg.sender != swap_info.user1 {
-                return Err(ContractError::Unauthorized {});
-            };
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::CodeManager)?;
 
-            // Check if coins are not native and the cw20 info is correct
-            if !swap_info.coin1.is_native {
-                if swap_info.coin1.cw20_address.is_none() {
-                    return Err(ContractError::InvalidCw20Addr {});
-                };
-                let res: TokenInfoResponse = deps.querier.query_wasm_smart(
-                    swap_info.coin1.cw20_address.as_ref().unwrap(),
-                    &Cw20QueryMsg::TokenInfo {},
-                )?;
-                if res.symbol != swap_info.coin1.coin.denom {
-                    return Err(FundsError::InvalidDenom {
-                        got: swap_info.coin1.coin.denom,
-                        expected: res.symbol,
-                    }
-                    .into());
-                };
-                if cw20_recieve_msg.amount != swap_info.coin1.coin.amount {
-                    return Err(FundsError::InvalidFunds {
-                        got: cw20_recieve_msg.amount.to_string(),
-                        expected: swap_info.coin1.coin.amount.to_string(),
-                    }
-                    .into());
-                };
-            };
-            if !swap_info.coin2.is_native {
-                if swap_info.coin2.cw20_address.is_none() {
-                    return Err(ContractError::InvalidCw20Addr {});
-                };
-                let res: TokenInfoResponse = deps.querier.query_wasm_smart(
-                    swap_info.coin2.cw20_address.as_ref().unwrap(),
-                    &Cw20QueryMsg::TokenInfo {},
-                )?;
-                if res.symbol != swap_info.coin2.coin.denom {
-                    return Err(FundsError::InvalidDenom {
-                        got: swap_info.coin2.coin.denom,
-                        expected: res.symbol,
-                    }
-                    .into());
-                };
-            };
+    // Swaps without a code id would fail to be created
+    if !enabled && code_id == config.cosmoswap_code_id {
+        return Err(ContractError::CannotRemoveDefaultCodeId { code_id });
+    }
+    CODE_IDS.save(
+        deps.storage,
+        code_id,
+        &CodeIdInfo {
+            version: version.clone(),
+            enabled,
+        },
+    )?;
 
-            execute_create_swap(deps, _env, info, swap_info, expiration)
-        }
+    Ok(Response::new()
+        .add_attribute("action", "set_code_id")
+        .add_attribute("code_id", code_id.to_string())
+        .add_attribute("version", version)
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+fn execute_remove_code_id(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    code_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::CodeManager)?;
+
+    if code_id == config.cosmoswap_code_id {
+        return Err(ContractError::CannotRemoveDefaultCodeId { code_id });
     }
+    CODE_IDS.remove(deps.storage, code_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_code_id")
+        .add_attribute("code_id", code_id.to_string()))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Config {} => to_binary(&query_config(deps, env)?),
-        QueryMsg::FeeConfig {} => to_binary(&query_fee_config(deps, env)?),
+fn assert_code_id_enabled(deps: Deps, code_id: u64) -> Result<CodeIdInfo, ContractError> {
+    match CODE_IDS.may_load(deps.storage, code_id)? {
+        Some(code_id_info) if code_id_info.enabled => Ok(code_id_info),
+        _ => Err(ContractError::CodeIdNotAllowed { code_id }),
     }
 }
 
-fn query_config(deps: Deps, _env: Env) -> StdResult<Config> {
+#[allow(clippy::too_many_arguments)]
+fn execute_propose_fee_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    fee_percentage: Decimal,
+    fee_payment_address: String,
+    fee_rounding: Rounding,
+    fee_bounds: Vec<FeeBound>,
+    fee_destination: FeeDestination,
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    Ok(config)
+    assert_role(deps.as_ref(), &config, &info.sender, Role::FeeManager)?;
+
+    let fee_config = build_fee_config(
+        deps.as_ref(),
+        fee_percentage,
+        fee_payment_address,
+        fee_rounding,
+        fee_bounds,
+        fee_destination,
+    )?;
+
+    // New fee config can only be applied after the timelock
+    let pending_fee_config = PendingFeeConfig {
+        fee_info: fee_config,
+        apply_after: env.block.time.plus_seconds(FEE_CONFIG_TIMELOCK),
+    };
+    PENDING_FEE_CONFIG.save(deps.storage, &pending_fee_config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_fee_config")
+        .add_attribute(
+            "fee_percentage",
+            pending_fee_config.fee_info.percentage.to_string(),
+        )
+        .add_attribute(
+            "fee_payment_address",
+            pending_fee_config.fee_info.payment_address.to_string(),
+        )
+        .add_attribute(
+            "apply_after",
+            pending_fee_config.apply_after.seconds().to_string(),
+        ))
 }
 
-fn query_fee_config(deps: Deps, _env: Env) -> StdResult<FeeInfo> {
-    let fee_config = FEE_CONFIG.load(deps.storage)?;
-    Ok(fee_config)
+fn execute_apply_fee_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::FeeManager)?;
+
+    let pending_fee_config = PENDING_FEE_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingFeeConfig {})?;
+    if env.block.time < pending_fee_config.apply_after {
+        return Err(ContractError::FeeConfigTimelocked {});
+    }
+
+    FEE_CONFIG.save(deps.storage, &pending_fee_config.fee_info)?;
+    PENDING_FEE_CONFIG.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "apply_fee_config")
+        .add_attribute(
+            "fee_percentage",
+            pending_fee_config.fee_info.percentage.to_string(),
+        )
+        .add_attribute(
+            "fee_payment_address",
+            pending_fee_config.fee_info.payment_address.to_string(),
+        ))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
-    if msg.id != INSTANTIATE_REPLY_ID {
-        return Err(ContractError::Unauthorized {});
+fn execute_cancel_fee_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::FeeManager)?;
+
+    if PENDING_FEE_CONFIG.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingFeeConfig {});
     }
+    PENDING_FEE_CONFIG.remove(deps.storage);
 
-    let reply_data = parse_reply_instantiate_data(msg.clone());
+    Ok(Response::new().add_attribute("action", "cancel_fee_config"))
+}
 
-    match reply_data {
-        Ok(reply_data) => {
-            let res = msg.result.into_result();
-            if res.is_err() {
-                return Err(ContractError::SwapInstantiateError {});
-            };
+/// Validates the fee parameters and builds the fee config
+fn build_fee_config(
+    deps: Deps,
+    fee_percentage: Decimal,
+    fee_payment_address: String,
+    fee_rounding: Rounding,
+    fee_bounds: Vec<FeeBound>,
+    fee_destination: FeeDestination,
+) -> Result<FeeInfo, ContractError> {
+    if fee_percentage > Decimal::percent(MAX_FEE_PERCENT) {
+        return Err(ContractError::InvalidFeePercentage {});
+    }
+    validate_fee_bounds(&fee_bounds)?;
 
-            let sub_msg_response = res.unwrap();
-
-            let mut coin1_cw20_addr = String::from("");
-            let mut amount = Uint128::zero();
-
-            sub_msg_response.events.iter().for_each(|e| {
-                if e.ty == "wasm" {
-                    e.attributes.iter().for_each(|attr| {
-                        if attr.key == "coin1_cw20_address" {
-                            coin1_cw20_addr = attr.value.clone();
-                        };
-                        if attr.key == "coin1_amount" {
-                            amount = Uint128::from_str(&attr.value).unwrap();
-                        };
-                    });
-                }
-            });
+    Ok(FeeInfo {
+        percentage: fee_percentage,
+        payment_address: deps.api.addr_validate(&fee_payment_address)?,
+        rounding: fee_rounding,
+        bounds: fee_bounds,
+        destination: fee_destination,
+    })
+}
 
-            let msg = WasmMsg::Execute {
-                contract_addr: coin1_cw20_addr,
-                msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                    recipient: reply_data.contract_address,
-                    amount,
-                })?,
-                funds: vec![],
-            };
+fn execute_update_fee_discount(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    source: DiscountSource,
+    mut tiers: Vec<FeeDiscountTier>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::FeeManager)?;
 
-            Ok(Response::new()
-                .add_message(msg)
-                .add_attribute("action", "cosmoswap_instantiate_reply"))
+    match &source {
+        DiscountSource::Balance { cw20_address } => {
+            deps.api.addr_validate(cw20_address)?;
         }
-        Err(_) => {
-            return Err(ContractError::SwapInstantiateError {});
+        DiscountSource::Staked { staking_contract } => {
+            deps.api.addr_validate(staking_contract)?;
         }
+    };
+
+    // Every tier needs a unique threshold and cannot discount more than the whole fee
+    tiers.sort_by_key(|tier| tier.min_amount);
+    if tiers.is_empty()
+        || tiers.iter().any(|tier| tier.discount > Decimal::one())
+        || tiers.windows(2).any(|w| w[0].min_amount == w[1].min_amount)
+    {
+        return Err(ContractError::InvalidDiscountTiers {});
     }
+
+    let fee_discount = FeeDiscount { source, tiers };
+    FEE_DISCOUNT.save(deps.storage, &fee_discount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_fee_discount")
+        .add_attribute("tiers", fee_discount.tiers.len().to_string()))
+}
+
+fn execute_remove_fee_discount(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::FeeManager)?;
+
+    FEE_DISCOUNT.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "remove_fee_discount"))
+}
+
+fn execute_set_pause(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    creation: bool,
+    settlement: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::Pauser)?;
+
+    let pause_info = PauseInfo {
+        creation,
+        settlement,
+    };
+    PAUSE_INFO.save(deps.storage, &pause_info)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_pause")
+        .add_attribute("creation", creation.to_string())
+        .add_attribute("settlement", settlement.to_string()))
+}
+
+fn execute_add_hook(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::ListCurator)?;
+
+    let hook = deps.api.addr_validate(&address)?;
+    if HOOKS.has(deps.storage, &hook) {
+        return Err(ContractError::HookAlreadyRegistered {});
+    }
+    HOOKS.save(deps.storage, &hook, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_hook")
+        .add_attribute("hook", hook))
+}
+
+fn execute_remove_hook(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::ListCurator)?;
+
+    let hook = deps.api.addr_validate(&address)?;
+    if !HOOKS.has(deps.storage, &hook) {
+        return Err(ContractError::HookNotRegistered {});
+    }
+    HOOKS.remove(deps.storage, &hook);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_hook")
+        .add_attribute("hook", hook))
+}
+
+fn execute_set_hook_gas_limit(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    gas_limit: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::ListCurator)?;
+
+    if gas_limit == 0 {
+        return Err(ContractError::InvalidHookGasLimit {});
+    }
+    HOOK_GAS_LIMIT.save(deps.storage, &gas_limit)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_hook_gas_limit")
+        .add_attribute("gas_limit", gas_limit.to_string()))
+}
+
+fn execute_set_creation_deposit(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    creation_deposit: Option<CreationDepositConfig>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::FeeManager)?;
+
+    match creation_deposit {
+        Some(creation_deposit) => {
+            if creation_deposit.amount.amount.is_zero()
+                || creation_deposit.forfeit_percentage > Decimal::one()
+            {
+                return Err(ContractError::InvalidCreationDeposit {});
+            }
+            CREATION_DEPOSIT_CONFIG.save(deps.storage, &creation_deposit)?;
+        }
+        None => CREATION_DEPOSIT_CONFIG.remove(deps.storage),
+    }
+
+    Ok(Response::new().add_attribute("action", "set_creation_deposit"))
+}
+
+/// Removes the creation deposit from the funds sent along with a swap
+fn take_creation_deposit(
+    mut info: MessageInfo,
+    amount: &Coin,
+) -> Result<MessageInfo, ContractError> {
+    let sent_fund = info
+        .funds
+        .iter_mut()
+        .find(|fund| fund.denom == amount.denom && fund.amount >= amount.amount)
+        .ok_or(ContractError::MissingCreationDeposit {})?;
+    sent_fund.amount -= amount.amount;
+    info.funds.retain(|fund| !fund.amount.is_zero());
+    Ok(info)
+}
+
+/// Returns the messages paying out the creation deposit once the swap is closed
+///
+/// The deposit is refunded to user1 unless the swap is cancelled before the minimum lifetime,
+/// which sends it to the fee payment address, or within the forfeit window after user2
+/// deposited, which pays the forfeit percentage to user2.
+fn creation_deposit_msgs(
+    deps: Deps,
+    env: &Env,
+    creation_deposit: &CreationDeposit,
+    cancelled: bool,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let terms = &creation_deposit.terms;
+    let mut maker_amount = terms.amount.amount;
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if cancelled {
+        let locked_recently = creation_deposit
+            .locked_at
+            .is_some_and(|locked_at| env.block.time < locked_at.plus_seconds(terms.forfeit_window));
+        if locked_recently {
+            let forfeit = maker_amount.mul_floor(terms.forfeit_percentage);
+            maker_amount = maker_amount.checked_sub(forfeit)?;
+            if !forfeit.is_zero() {
+                msgs.push(
+                    BankMsg::Send {
+                        to_address: creation_deposit.taker.to_string(),
+                        amount: vec![coin(forfeit.u128(), &terms.amount.denom)],
+                    }
+                    .into(),
+                );
+            }
+        }
+    }
+
+    let recipient = if cancelled
+        && env.block.time < creation_deposit.created_at.plus_seconds(terms.min_lifetime)
+    {
+        FEE_CONFIG.load(deps.storage)?.payment_address
+    } else {
+        creation_deposit.maker.clone()
+    };
+    if !maker_amount.is_zero() {
+        msgs.push(
+            BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![coin(maker_amount.u128(), &terms.amount.denom)],
+            }
+            .into(),
+        );
+    }
+    Ok(msgs)
+}
+
+fn execute_set_sweep_bounty(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    bounty: Option<Coin>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_role(deps.as_ref(), &config, &info.sender, Role::FeeManager)?;
+
+    match bounty {
+        Some(bounty) => {
+            if bounty.amount.is_zero() {
+                return Err(ContractError::InvalidSweepBounty {});
+            }
+            SWEEP_BOUNTY.save(deps.storage, &bounty)?;
+        }
+        None => SWEEP_BOUNTY.remove(deps.storage),
+    }
+
+    Ok(Response::new().add_attribute("action", "set_sweep_bounty"))
+}
+
+fn execute_fund_sweep_pool(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if info.funds.is_empty() {
+        return Err(FundsError::MissingFunds {}.into());
+    }
+    for fund in &info.funds {
+        SWEEP_POOL.update(deps.storage, &fund.denom, |pool| -> StdResult<_> {
+            Ok(pool.unwrap_or_default().checked_add(fund.amount)?)
+        })?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_sweep_pool")
+        .add_attribute("sender", info.sender))
+}
+
+/// Returns coin1 of expired swaps to user1 and pays the bounty to the sender
+///
+/// The bounty is taken out of the creation deposit of the swap when both share the denom,
+/// otherwise the sweep pool pays it if it can cover it. The rest of the deposit is paid out like
+/// for a cancelled swap. Makers sweeping their own swaps are not
+/// paid so the pool can not be farmed with short lived swaps. Disputed swaps are left to the
+/// arbiter.
+fn execute_sweep_expired(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let bounty = SWEEP_BOUNTY.may_load(deps.storage)?;
+
+    // Indexes are only ranged up to the current block, so every visited swap is expired. Each
+    // one is either swept or dropped from the index, so the limit bounds the queries made and
+    // no entry is visited twice.
+    let mut expired = EXPIRING_AT_HEIGHT
+        .keys(
+            deps.storage,
+            None,
+            Some(Bound::inclusive((env.block.height, u64::MAX))),
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|key| key.map(|(_, swap_id)| swap_id))
+        .collect::<StdResult<Vec<u64>>>()?;
+    expired.extend(
+        EXPIRING_AT_TIME
+            .keys(
+                deps.storage,
+                None,
+                Some(Bound::inclusive((env.block.time.nanos(), u64::MAX))),
+                Order::Ascending,
+            )
+            .take(limit - expired.len())
+            .map(|key| key.map(|(_, swap_id)| swap_id))
+            .collect::<StdResult<Vec<u64>>>()?,
+    );
+    if expired.is_empty() {
+        return Err(ContractError::NothingToSweep {});
+    }
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut swept: Vec<String> = vec![];
+    let mut dropped: Vec<String> = vec![];
+    let mut keeper_amount = Uint128::zero();
+    for swap_id in expired {
+        remove_expiration(deps.storage, swap_id)?;
+
+        // Disputed swaps are closed by the arbiter, the rest is already settled
+        let swap_addr = SWAPS.load(deps.storage, swap_id)?;
+        let status: CosmoswapStatus = deps
+            .querier
+            .query_wasm_smart(&swap_addr, &CosmoswapQueryMsg::Status {})?;
+        if !matches!(
+            status,
+            CosmoswapStatus::Open | CosmoswapStatus::Deposited { .. }
+        ) {
+            dropped.push(swap_id.to_string());
+            continue;
+        }
+
+        // Hashed time-locked swaps are refunded, the rest is cancelled by the controller
+        let swap_config: CosmoswapConfig = deps
+            .querier
+            .query_wasm_smart(&swap_addr, &CosmoswapQueryMsg::Config {})?;
+        let swap_msg = match swap_config.hash_lock {
+            Some(_) => CosmoswapExecuteMsg::Refund {},
+            None => CosmoswapExecuteMsg::Cancel {},
+        };
+        msgs.push(
+            WasmMsg::Execute {
+                contract_addr: swap_addr.to_string(),
+                msg: to_json_binary(&swap_msg)?,
+                funds: vec![],
+            }
+            .into(),
+        );
+
+        SWAP_STATUSES.save(
+            deps.storage,
+            swap_id,
+            &RegistryStatus::Swept {
+                keeper: info.sender.clone(),
+            },
+        )?;
+        let swap: CosmoswapSwap = deps
+            .querier
+            .query_wasm_smart(&swap_addr, &CosmoswapQueryMsg::Swap {})?;
+        let swap_bounty = bounty.as_ref().filter(|_| swap.user1 != info.sender);
+
+        // Creation deposit is settled here so the swap notification does not pay it out again
+        let mut bounty_paid = false;
+        if let Some(mut creation_deposit) = CREATION_DEPOSITS.may_load(deps.storage, swap_id)? {
+            CREATION_DEPOSITS.remove(deps.storage, swap_id);
+            let deposit = &mut creation_deposit.terms.amount;
+            if let Some(bounty) = swap_bounty.filter(|bounty| bounty.denom == deposit.denom) {
+                let amount = bounty.amount.min(deposit.amount);
+                deposit.amount = deposit.amount.checked_sub(amount)?;
+                keeper_amount = keeper_amount.checked_add(amount)?;
+                bounty_paid = true;
+            }
+            // Sweeping counts as cancelling so makers can not dodge the penalties with it
+            msgs.extend(creation_deposit_msgs(
+                deps.as_ref(),
+                &env,
+                &creation_deposit,
+                true,
+            )?);
+        }
+        if let Some(bounty) = swap_bounty.filter(|_| !bounty_paid) {
+            let pool = SWEEP_POOL
+                .may_load(deps.storage, &bounty.denom)?
+                .unwrap_or_default();
+            if pool >= bounty.amount {
+                let remaining = pool.checked_sub(bounty.amount)?;
+                if remaining.is_zero() {
+                    SWEEP_POOL.remove(deps.storage, &bounty.denom);
+                } else {
+                    SWEEP_POOL.save(deps.storage, &bounty.denom, &remaining)?;
+                }
+                keeper_amount = keeper_amount.checked_add(bounty.amount)?;
+            }
+        }
+
+        swept.push(swap_id.to_string());
+    }
+
+    if let Some(bounty) = bounty.filter(|_| !keeper_amount.is_zero()) {
+        msgs.push(
+            BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![coin(keeper_amount.u128(), bounty.denom)],
+            }
+            .into(),
+        );
+    }
+
+    let mut res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "sweep_expired")
+        .add_attribute("keeper", info.sender)
+        .add_attribute("bounty", keeper_amount);
+    if !swept.is_empty() {
+        res = res.add_attribute("swap_ids", swept.join(","));
+    }
+    if !dropped.is_empty() {
+        res = res.add_attribute("dropped_swap_ids", dropped.join(","));
+    }
+    Ok(res)
+}
+
+fn save_expiration(
+    storage: &mut dyn Storage,
+    swap_id: u64,
+    expiration: &Expiration,
+) -> StdResult<()> {
+    match expiration {
+        Expiration::AtHeight(height) => {
+            EXPIRING_AT_HEIGHT.save(storage, (*height, swap_id), &Empty {})?
+        }
+        Expiration::AtTime(time) => {
+            EXPIRING_AT_TIME.save(storage, (time.nanos(), swap_id), &Empty {})?
+        }
+        Expiration::Never {} => return Ok(()),
+    }
+    EXPIRING_SWAPS.save(storage, swap_id, expiration)
+}
+
+fn remove_expiration(storage: &mut dyn Storage, swap_id: u64) -> StdResult<()> {
+    match EXPIRING_SWAPS.may_load(storage, swap_id)? {
+        Some(Expiration::AtHeight(height)) => EXPIRING_AT_HEIGHT.remove(storage, (height, swap_id)),
+        Some(Expiration::AtTime(time)) => EXPIRING_AT_TIME.remove(storage, (time.nanos(), swap_id)),
+        _ => {}
+    }
+    EXPIRING_SWAPS.remove(storage, swap_id);
+    Ok(())
+}
+
+fn execute_notify_swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    event: SwapEvent,
+) -> Result<Response, ContractError> {
+    // Only swaps created by the controller can notify
+    let swap_id = SWAP_IDS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::Unauthorized {})?;
+
+    // Swept swaps keep their registry status
+    let closing = !matches!(event, SwapEvent::Deposited | SwapEvent::Withdrawn);
+    if closing && SWAP_STATUSES.may_load(deps.storage, swap_id)? == Some(RegistryStatus::Open {}) {
+        SWAP_STATUSES.save(
+            deps.storage,
+            swap_id,
+            &RegistryStatus::Closed {
+                event: event.clone(),
+            },
+        )?;
+        remove_expiration(deps.storage, swap_id)?;
+    }
+
+    let mut res = Response::new();
+    if let Some(mut creation_deposit) = CREATION_DEPOSITS.may_load(deps.storage, swap_id)? {
+        match event {
+            SwapEvent::Deposited => {
+                creation_deposit.locked_at = Some(env.block.time);
+                CREATION_DEPOSITS.save(deps.storage, swap_id, &creation_deposit)?;
+            }
+            SwapEvent::Withdrawn => {
+                creation_deposit.locked_at = None;
+                CREATION_DEPOSITS.save(deps.storage, swap_id, &creation_deposit)?;
+            }
+            _ => {
+                let cancelled = event == SwapEvent::Cancelled;
+                res = res.add_messages(creation_deposit_msgs(
+                    deps.as_ref(),
+                    &env,
+                    &creation_deposit,
+                    cancelled,
+                )?);
+                CREATION_DEPOSITS.remove(deps.storage, swap_id);
+            }
+        }
+    }
+
+    let hook_msg = SwapHookMsg::new(event, swap_id, info.sender);
+    Ok(res
+        .add_submessages(hook_msgs(deps.storage, hook_msg)?)
+        .add_attribute("action", "notify_swap")
+        .add_attribute("swap_id", swap_id.to_string()))
+}
+
+/// Returns the hook message for every subscriber
+///
+/// Failing subscribers are caught on reply so they cannot block settlement, the gas limit makes
+/// running out of gas a caught error as well.
+fn hook_msgs(storage: &dyn Storage, hook_msg: SwapHookMsg) -> StdResult<Vec<SubMsg>> {
+    let gas_limit = HOOK_GAS_LIMIT
+        .may_load(storage)?
+        .unwrap_or(DEFAULT_HOOK_GAS_LIMIT);
+    HOOKS
+        .keys(storage, None, None, Order::Ascending)
+        .map(|hook| {
+            let msg = hook_msg.clone().into_cosmos_msg(hook?)?;
+            Ok(SubMsg::reply_on_error(msg, HOOK_REPLY_ID).with_gas_limit(gas_limit))
+        })
+        .collect()
+}
+
+/// Returns the fee percentage for the user after applying the discount tier it qualifies for
+fn get_effective_fee(
+    deps: Deps,
+    fee_config: &FeeInfo,
+    user: &str,
+) -> Result<(Decimal, Option<u32>), ContractError> {
+    let fee_discount = match FEE_DISCOUNT.may_load(deps.storage)? {
+        Some(fee_discount) => fee_discount,
+        None => return Ok((fee_config.percentage, None)),
+    };
+
+    let amount = match fee_discount.source {
+        DiscountSource::Balance { cw20_address } => {
+            let res: BalanceResponse = deps.querier.query_wasm_smart(
+                cw20_address,
+                &Cw20QueryMsg::Balance {
+                    address: user.to_string(),
+                },
+            )?;
+            res.balance
+        }
+        DiscountSource::Staked { staking_contract } => {
+            let res: StakedBalanceAtHeightResponse = deps.querier.query_wasm_smart(
+                staking_contract,
+                &StakingQueryMsg::StakedBalanceAtHeight {
+                    address: user.to_string(),
+                    height: None,
+                },
+            )?;
+            res.balance
+        }
+    };
+
+    // Pick the highest tier the user qualifies for
+    let tier = fee_discount
+        .tiers
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, tier)| amount >= tier.min_amount);
+
+    match tier {
+        Some((index, tier)) => Ok((
+            fee_config.percentage * (Decimal::one() - tier.discount),
+            Some(index as u32),
+        )),
+        None => Ok((fee_config.percentage, None)),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_create_swap(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    swap_info: SwapInfo,
+    expiration: Expiration,
+    code_id: Option<u64>,
+    hash_lock: Option<HexBinary>,
+    cw20_received: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut fee_config = FEE_CONFIG.load(deps.storage)?;
+
+    let pause_info = PAUSE_INFO.load(deps.storage)?;
+    if pause_info.creation {
+        return Err(ContractError::CreationPaused {});
+    }
+
+    // Remaining funds are forwarded to the swap. Cw20 swaps can not attach native funds, so
+    // their deposit is taken from the Deposit balance of user1 instead.
+    let creation_deposit_config = CREATION_DEPOSIT_CONFIG.may_load(deps.storage)?;
+    let info = match &creation_deposit_config {
+        Some(terms) if cw20_received => {
+            let user1 = deps.api.addr_validate(&swap_info.user1)?;
+            withdraw_deposit(deps.branch(), &user1, &terms.amount)
+                .map_err(|_| ContractError::MissingCreationDeposit {})?;
+            info
+        }
+        Some(terms) => take_creation_deposit(info, &terms.amount)?,
+        None => info,
+    };
+
+    if expiration.is_expired(&env.block) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+
+    // Hashed time-locked swaps need a sha256 hash and a timeout for refunds
+    if let Some(hash_lock) = &hash_lock {
+        if hash_lock.len() != 32 {
+            return Err(ContractError::InvalidHashLock {});
+        }
+        if expiration == (Expiration::Never {}) {
+            return Err(ContractError::InvalidExpiration {});
+        }
+    }
+
+    if swap_info.user1 == swap_info.user2 {
+        return Err(ContractError::SameUsers {});
+    };
+
+    if swap_info.coin1.coin.denom == swap_info.coin2.coin.denom {
+        return Err(ContractError::SameDenoms {});
+    };
+
+    validate_recipient(deps.as_ref(), &swap_info.user1_recipient, &swap_info.coin2)?;
+    validate_arbiter(deps.as_ref(), &swap_info, &hash_lock)?;
+    if swap_info.confirmation_window.is_some()
+        && (hash_lock.is_some() || swap_info.confirmation_window == Some(0))
+    {
+        return Err(ContractError::InvalidConfirmationWindow {});
+    }
+    validate_pricing(&swap_info, &hash_lock)?;
+
+    // Funds left after the creation deposit would be stuck in a cw20 swap
+    if swap_info.coin1.is_native {
+        check_single_coin(&info, &swap_info.coin1.coin)?;
+    } else if !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedFunds {});
+    };
+
+    // Bake the discounted fee into the swap contract
+    let (percentage, tier) = get_effective_fee(deps.as_ref(), &fee_config, &swap_info.user1)?;
+    fee_config.percentage = percentage;
+
+    // Only pass the bounds of the swapped assets and make sure both sides can cover the fees
+    let (asset1, asset2) = (swap_info.coin1.asset(), swap_info.coin2.asset());
+    fee_config
+        .bounds
+        .retain(|bound| bound.asset == asset1 || bound.asset == asset2);
+    calculate_fee(&fee_config, &swap_info.coin1)?;
+    calculate_fee(&fee_config, &swap_info.coin2)?;
+
+    let code_id = code_id.unwrap_or(config.cosmoswap_code_id);
+    assert_code_id_enabled(deps.as_ref(), code_id)?;
+
+    // Swap address is derived from the maker and the nonce so it is known before instantiation
+    let maker = deps.api.addr_validate(&swap_info.user1)?;
+    let nonce = SWAP_NONCES
+        .may_load(deps.storage, &maker)?
+        .unwrap_or_default();
+    SWAP_NONCES.save(deps.storage, &maker, &(nonce + 1))?;
+    let salt = swap_salt(&maker, nonce);
+    let swap_addr = predict_swap_address(deps.as_ref(), &env, code_id, &salt)?;
+
+    // Register the swap so it can be verified later on
+    let swap_id = SWAP_COUNT.load(deps.storage)? + 1;
+    SWAP_COUNT.save(deps.storage, &swap_id)?;
+    SWAPS.save(deps.storage, swap_id, &swap_addr)?;
+    SWAP_IDS.save(deps.storage, &swap_addr, &swap_id)?;
+    SWAP_STATUSES.save(deps.storage, swap_id, &RegistryStatus::Open {})?;
+    save_expiration(deps.storage, swap_id, &expiration)?;
+
+    // Anti-spam deposit is held by the controller until the swap is closed
+    if let Some(terms) = creation_deposit_config {
+        CREATION_DEPOSITS.save(
+            deps.storage,
+            swap_id,
+            &CreationDeposit {
+                maker: maker.clone(),
+                taker: deps.api.addr_validate(&swap_info.user2)?,
+                terms,
+                created_at: env.block.time,
+                locked_at: None,
+            },
+        )?;
+    }
+
+    let wasm_msg = WasmMsg::Instantiate2 {
+        code_id,
+        msg: to_json_binary(&CosmoswapInstantiateMsg {
+            fee_info: fee_config,
+            swap_info: swap_info.clone(),
+            expiration,
+            hash_lock,
+        })?,
+        funds: info.funds.clone(),
+        admin: None,
+        label: "Cosmoswap Contract".to_string(),
+        salt,
+    };
+
+    // Reply is used to verify the instantiated contract
+    PENDING_INSTANTIATE.save(
+        deps.storage,
+        &PendingInstantiate {
+            code_id,
+            address: swap_addr.clone(),
+        },
+    )?;
+    let mut res =
+        Response::new().add_submessage(SubMsg::reply_on_success(wasm_msg, INSTANTIATE_REPLY_ID));
+
+    // cw20 tokens are sent by the controller or pulled from user1 after instantiation
+    if !swap_info.coin1.is_native {
+        let msg = if cw20_received {
+            transfer_msg(&swap_info.coin1, &swap_addr, swap_info.coin1.coin.amount)?
+        } else {
+            transfer_from_msg(&swap_info.coin1, &maker, &swap_addr)?
+        };
+        res = res.add_message(msg);
+    }
+
+    Ok(res
+        .add_attribute("action", "create_swap")
+        .add_attribute("swap_id", swap_id.to_string())
+        .add_attribute("swap_address", swap_addr)
+        .add_attribute("nonce", nonce.to_string())
+        .add_attribute("code_id", code_id.to_string())
+        .add_attribute("fee_percentage", percentage.to_string())
+        .add_attribute(
+            "fee_discount_tier",
+            tier.map_or("none".to_string(), |tier| tier.to_string()),
+        ))
+}
+
+fn validate_recipient(
+    deps: Deps,
+    recipient: &Option<Recipient>,
+    swap_coin: &SwapCoin,
+) -> Result<(), ContractError> {
+    match recipient {
+        None => {}
+        Some(Recipient::Address { address }) | Some(Recipient::Contract { address, .. }) => {
+            deps.api.addr_validate(address)?;
+        }
+        // Proceeds can only be sent over IBC for native coins
+        Some(Recipient::Ibc(ibc_recipient))
+            if !swap_coin.is_native
+                || ibc_recipient.channel_id.is_empty()
+                || ibc_recipient.receiver.is_empty()
+                || ibc_recipient.timeout == 0 =>
+        {
+            return Err(ContractError::InvalidIbcRecipient {});
+        }
+        Some(Recipient::Ibc(_)) => {}
+    }
+    Ok(())
+}
+
+// Disputes are not supported for hashed time-locked swaps
+fn validate_arbiter(
+    deps: Deps,
+    swap_info: &SwapInfo,
+    hash_lock: &Option<HexBinary>,
+) -> Result<(), ContractError> {
+    if let Some(arbiter) = &swap_info.arbiter {
+        deps.api.addr_validate(&arbiter.address)?;
+        if hash_lock.is_some()
+            || arbiter.address == swap_info.user1
+            || arbiter.address == swap_info.user2
+            || arbiter.fee_percentage >= Decimal::one()
+        {
+            return Err(ContractError::InvalidArbiter {});
+        }
+    }
+    Ok(())
+}
+
+/// Dutch auctions settle on accept so they can not be hash locked or confirmed later
+fn validate_pricing(
+    swap_info: &SwapInfo,
+    hash_lock: &Option<HexBinary>,
+) -> Result<(), ContractError> {
+    if let Some(pricing) = &swap_info.pricing {
+        let invalid_decay = match &pricing.decay {
+            PriceDecay::Linear => false,
+            PriceDecay::Exponential {
+                percentage,
+                interval,
+            } => *interval == 0 || percentage.is_zero() || *percentage > Decimal::one(),
+        };
+        if hash_lock.is_some()
+            || swap_info.confirmation_window.is_some()
+            || pricing.start_amount <= swap_info.coin2.coin.amount
+            || pricing.start_time >= pricing.end_time
+            || invalid_decay
+        {
+            return Err(ContractError::InvalidPricing {});
+        }
+    }
+    Ok(())
+}
+
+/// Checks the cw20 address is set and the token symbol matches the denom
+fn validate_cw20_coin(deps: Deps, swap_coin: &SwapCoin) -> Result<(), ContractError> {
+    let cw20_address = swap_coin
+        .cw20_address
+        .as_ref()
+        .ok_or(ContractError::InvalidCw20Addr {})?;
+    let res: TokenInfoResponse = deps
+        .querier
+        .query_wasm_smart(cw20_address, &Cw20QueryMsg::TokenInfo {})?;
+    if res.symbol != swap_coin.coin.denom {
+        return Err(FundsError::InvalidDenom {
+            got: swap_coin.coin.denom.clone(),
+            expected: res.symbol,
+        }
+        .into());
+    };
+    Ok(())
+}
+
+fn execute_deposit(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    if info.funds.is_empty() {
+        return Err(FundsError::MissingFunds {}.into());
+    }
+    for fund in &info.funds {
+        DEPOSITS.update(
+            deps.storage,
+            (&info.sender, &fund.denom),
+            |deposit| -> StdResult<_> { Ok(deposit.unwrap_or_default().checked_add(fund.amount)?) },
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "deposit")
+        .add_attribute("sender", info.sender))
+}
+
+fn execute_withdraw(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    amount: Coin,
+) -> Result<Response, ContractError> {
+    withdraw_deposit(deps, &info.sender, &amount)?;
+
+    let msg = BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![amount],
+    };
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "withdraw")
+        .add_attribute("sender", info.sender))
+}
+
+fn withdraw_deposit(deps: DepsMut, owner: &Addr, amount: &Coin) -> Result<(), ContractError> {
+    let deposit = DEPOSITS
+        .may_load(deps.storage, (owner, &amount.denom))?
+        .unwrap_or_default();
+    let remaining = deposit
+        .checked_sub(amount.amount)
+        .map_err(|_| ContractError::InsufficientDeposit {})?;
+    if remaining.is_zero() {
+        DEPOSITS.remove(deps.storage, (owner, &amount.denom));
+    } else {
+        DEPOSITS.save(deps.storage, (owner, &amount.denom), &remaining)?;
+    }
+    Ok(())
+}
+
+fn execute_cancel_nonce(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    nonce: u64,
+) -> Result<Response, ContractError> {
+    if USED_NONCES.has(deps.storage, (&info.sender, nonce)) {
+        return Err(ContractError::NonceUsed { nonce });
+    }
+    USED_NONCES.save(deps.storage, (&info.sender, nonce), &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_nonce")
+        .add_attribute("nonce", nonce.to_string()))
+}
+
+/// Settles a signed offer, coin2 of the taker is already received by the controller
+fn execute_take_offer(
+    mut deps: DepsMut,
+    env: Env,
+    taker: Addr,
+    offer: Offer,
+    signature: Binary,
+    pubkey: Binary,
+) -> Result<Response, ContractError> {
+    let pause_info = PAUSE_INFO.load(deps.storage)?;
+    if pause_info.settlement {
+        return Err(ContractError::SettlementPaused {});
+    }
+
+    let swap_info = &offer.swap_info;
+    // Offers are settled by the controller so only plain swaps are supported
+    if offer.controller != env.contract.address
+        || swap_info.user1_recipient.is_some()
+        || swap_info.arbiter.is_some()
+        || swap_info.confirmation_window.is_some()
+        || swap_info.pricing.is_some()
+    {
+        return Err(ContractError::InvalidOffer {});
+    }
+    if offer.expiration.is_expired(&env.block) {
+        return Err(ContractError::OfferExpired {});
+    }
+    if taker != swap_info.user2 {
+        return Err(ContractError::Unauthorized {});
+    }
+    if swap_info.user1 == swap_info.user2 {
+        return Err(ContractError::SameUsers {});
+    }
+    if swap_info.coin1.coin.denom == swap_info.coin2.coin.denom {
+        return Err(ContractError::SameDenoms {});
+    }
+    if !swap_info.coin1.is_native && swap_info.coin1.cw20_address.is_none() {
+        return Err(ContractError::InvalidCw20Addr {});
+    }
+
+    let maker = deps.api.addr_validate(&swap_info.user1)?;
+    verify_offer_signature(deps.as_ref(), &maker, &offer, &signature, &pubkey)?;
+
+    // Nonces can only be used once
+    if USED_NONCES.has(deps.storage, (&maker, offer.nonce)) {
+        return Err(ContractError::NonceUsed { nonce: offer.nonce });
+    }
+    USED_NONCES.save(deps.storage, (&maker, offer.nonce), &Empty {})?;
+
+    // Same fee rules as swaps created on the controller
+    let mut fee_config = FEE_CONFIG.load(deps.storage)?;
+    let (percentage, _) = get_effective_fee(deps.as_ref(), &fee_config, &swap_info.user1)?;
+    fee_config.percentage = percentage;
+    let (asset1, asset2) = (swap_info.coin1.asset(), swap_info.coin2.asset());
+    fee_config
+        .bounds
+        .retain(|bound| bound.asset == asset1 || bound.asset == asset2);
+    let coin1_fee = calculate_fee(&fee_config, &swap_info.coin1)?;
+    let coin2_fee = calculate_fee(&fee_config, &swap_info.coin2)?;
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+
+    // Maker side is pulled from the deposit or the cw20 allowance
+    if swap_info.coin1.is_native {
+        withdraw_deposit(deps.branch(), &maker, &swap_info.coin1.coin)?;
+    } else {
+        check_cw20_allowance(
+            &deps.querier,
+            &env.block,
+            &swap_info.coin1,
+            &maker,
+            &env.contract.address,
+        )?;
+        msgs.push(transfer_from_msg(
+            &swap_info.coin1,
+            &maker,
+            &env.contract.address,
+        )?);
+    }
+    if !coin1_fee.is_zero() {
+        msgs.push(fee_msg(&fee_config, &swap_info.coin1, coin1_fee)?);
+    }
+    msgs.push(transfer_msg(
+        &swap_info.coin1,
+        &taker,
+        swap_info.coin1.coin.amount.checked_sub(coin1_fee)?,
+    )?);
+
+    if !coin2_fee.is_zero() {
+        msgs.push(fee_msg(&fee_config, &swap_info.coin2, coin2_fee)?);
+    }
+    msgs.push(transfer_msg(
+        &swap_info.coin2,
+        &maker,
+        swap_info.coin2.coin.amount.checked_sub(coin2_fee)?,
+    )?);
+
+    let hook_msg = SwapHookMsg::OfferTaken {
+        maker: maker.clone(),
+        taker: taker.clone(),
+        nonce: offer.nonce,
+    };
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessages(hook_msgs(deps.storage, hook_msg)?)
+        .add_attribute("action", "take_offer")
+        .add_attribute("maker", maker)
+        .add_attribute("taker", taker)
+        .add_attribute("nonce", offer.nonce.to_string()))
+}
+
+/// Verifies the ADR-036 signature of the offer and that the public key belongs to the maker
+fn verify_offer_signature(
+    deps: Deps,
+    maker: &Addr,
+    offer: &Offer,
+    signature: &Binary,
+    pubkey: &Binary,
+) -> Result<(), ContractError> {
+    let pubkey_hash = Ripemd160::digest(Sha256::digest(pubkey.as_slice()));
+    if deps.api.addr_canonicalize(maker.as_str())?.as_slice() != pubkey_hash.as_slice() {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    let sign_doc = adr036_sign_doc(maker, &to_json_binary(offer)?);
+    let hash = Sha256::digest(sign_doc.as_bytes());
+    let valid = deps
+        .api
+        .secp256k1_verify(&hash, signature, pubkey)
+        .map_err(|_| ContractError::InvalidSignature {})?;
+    if !valid {
+        return Err(ContractError::InvalidSignature {});
+    }
+    Ok(())
+}
+
+/// Amino JSON sign doc wallets produce when signing arbitrary data
+pub fn adr036_sign_doc(signer: &Addr, data: &Binary) -> String {
+    format!(
+        r#"{{"account_number":"0","chain_id":"","fee":{{"amount":[],"gas":"0"}},"memo":"","msgs":[{{"type":"sign/MsgSignData","value":{{"data":"{}","signer":"{}"}}}}],"sequence":"0"}}"#,
+        data.to_base64(),
+        signer
+    )
+}
+
+fn swap_salt(maker: &Addr, nonce: u64) -> Binary {
+    let mut hasher = Sha256::new();
+    hasher.update(maker.as_bytes());
+    hasher.update(nonce.to_be_bytes());
+    Binary::from(hasher.finalize().to_vec())
+}
+
+fn predict_swap_address(deps: Deps, env: &Env, code_id: u64, salt: &Binary) -> StdResult<Addr> {
+    let code_info = deps.querier.query_wasm_code_info(code_id)?;
+    let creator = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+    let address = instantiate2_address(&code_info.checksum, &creator, salt)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    deps.api.addr_humanize(&address)
+}
+
+fn execute_receive(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    cw20_recieve_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let msg: ReceiveMsg = from_json(&cw20_recieve_msg.msg)?;
+    match msg {
+        ReceiveMsg::CreateSwap {
+            swap_info,
+            expiration,
+            code_id,
+            hash_lock,
+        } => {
+            // Check if the sender is the same as the user1
+            if cw20_recieve_msg.sender != swap_info.user1 {
+                return Err(ContractError::Unauthorized {});
+            };
+
+            // Check if coins are not native and the cw20 info is correct
+            if !swap_info.coin1.is_native {
+                validate_cw20_coin(deps.as_ref(), &swap_info.coin1)?;
+                if cw20_recieve_msg.amount != swap_info.coin1.coin.amount {
+                    return Err(FundsError::InvalidFunds {
+                        got: cw20_recieve_msg.amount.to_string(),
+                        expected: swap_info.coin1.coin.amount.to_string(),
+                    }
+                    .into());
+                };
+            };
+            if !swap_info.coin2.is_native {
+                validate_cw20_coin(deps.as_ref(), &swap_info.coin2)?;
+            };
+
+            execute_create_swap(
+                deps, _env, info, swap_info, expiration, code_id, hash_lock, true,
+            )
+        }
+        ReceiveMsg::TakeOffer {
+            offer,
+            signature,
+            pubkey,
+        } => {
+            let coin2 = &offer.swap_info.coin2;
+            if coin2.is_native || coin2.cw20_address.as_deref() != Some(info.sender.as_str()) {
+                return Err(ContractError::InvalidOffer {});
+            }
+            if cw20_recieve_msg.amount != coin2.coin.amount {
+                return Err(FundsError::InvalidFunds {
+                    got: cw20_recieve_msg.amount.to_string(),
+                    expected: coin2.coin.amount.to_string(),
+                }
+                .into());
+            }
+            let taker = deps.api.addr_validate(&cw20_recieve_msg.sender)?;
+            execute_take_offer(deps, _env, taker, offer, signature, pubkey)
+        }
+        ReceiveMsg::AcceptSwap { swap_id, recipient } => {
+            let swap_addr = SWAPS
+                .may_load(deps.storage, swap_id)?
+                .ok_or(ContractError::SwapNotFound {})?;
+
+            // Token, amount and taker are validated by the swap
+            let msg = WasmMsg::Execute {
+                contract_addr: info.sender.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: swap_addr.to_string(),
+                    amount: cw20_recieve_msg.amount,
+                    msg: to_json_binary(&CosmoswapReceiveMsg::AcceptFor {
+                        taker: cw20_recieve_msg.sender,
+                        recipient,
+                    })?,
+                })?,
+                funds: vec![],
+            };
+
+            Ok(Response::new()
+                .add_message(msg)
+                .add_attribute("action", "accept_swap")
+                .add_attribute("swap_id", swap_id.to_string())
+                .add_attribute("swap_address", swap_addr))
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps, env)?),
+        QueryMsg::CodeIds { start_after, limit } => {
+            to_json_binary(&query_code_ids(deps, env, start_after, limit)?)
+        }
+        QueryMsg::PendingAdmin {} => to_json_binary(&query_pending_admin(deps, env)?),
+        QueryMsg::RoleHolders {
+            role,
+            start_after,
+            limit,
+        } => to_json_binary(&query_role_holders(deps, env, role, start_after, limit)?),
+        QueryMsg::FeeConfig {} => to_json_binary(&query_fee_config(deps, env)?),
+        QueryMsg::PendingFeeConfig {} => to_json_binary(&query_pending_fee_config(deps, env)?),
+        QueryMsg::PauseInfo {} => to_json_binary(&query_pause_info(deps, env)?),
+        QueryMsg::FeeDiscount {} => to_json_binary(&query_fee_discount(deps, env)?),
+        QueryMsg::EffectiveFee { address } => {
+            to_json_binary(&query_effective_fee(deps, env, address)?)
+        }
+        QueryMsg::Hooks { start_after, limit } => {
+            to_json_binary(&query_hooks(deps, env, start_after, limit)?)
+        }
+        QueryMsg::HookGasLimit {} => to_json_binary(&query_hook_gas_limit(deps, env)?),
+        QueryMsg::Deposits { address } => to_json_binary(&query_deposits(deps, env, address)?),
+        QueryMsg::IsNonceUsed { maker, nonce } => {
+            to_json_binary(&query_is_nonce_used(deps, env, maker, nonce)?)
+        }
+        QueryMsg::IsRegisteredSwap { address } => {
+            to_json_binary(&query_is_registered_swap(deps, env, address)?)
+        }
+        QueryMsg::SwapStatus { swap_id } => to_json_binary(&query_swap_status(deps, env, swap_id)?),
+        QueryMsg::SweepBounty {} => to_json_binary(&query_sweep_bounty(deps, env)?),
+        QueryMsg::CreationDepositConfig {} => {
+            to_json_binary(&query_creation_deposit_config(deps, env)?)
+        }
+        QueryMsg::CreationDeposit { swap_id } => {
+            to_json_binary(&query_creation_deposit(deps, env, swap_id)?)
+        }
+        QueryMsg::PredictSwapAddress {
+            maker,
+            nonce,
+            code_id,
+        } => to_json_binary(&query_predict_swap_address(
+            deps, env, maker, nonce, code_id,
+        )?),
+    }
+}
+
+fn query_config(deps: Deps, _env: Env) -> StdResult<Config> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(config)
+}
+
+fn query_code_ids(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<CodeIdsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let code_ids = CODE_IDS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (code_id, code_id_info) = item?;
+            Ok(CodeIdResponse {
+                code_id,
+                version: code_id_info.version,
+                enabled: code_id_info.enabled,
+            })
+        })
+        .collect::<StdResult<Vec<CodeIdResponse>>>()?;
+    Ok(CodeIdsResponse { code_ids })
+}
+
+fn query_pending_admin(deps: Deps, _env: Env) -> StdResult<Option<PendingAdmin>> {
+    let pending_admin = PENDING_ADMIN.may_load(deps.storage)?;
+    Ok(pending_admin)
+}
+
+fn query_role_holders(
+    deps: Deps,
+    _env: Env,
+    role: Role,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<RoleHoldersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let holders = ROLES
+        .prefix(role.as_str())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<Addr>>>()?;
+    Ok(RoleHoldersResponse { holders })
+}
+
+fn query_fee_config(deps: Deps, _env: Env) -> StdResult<FeeInfo> {
+    let fee_config = FEE_CONFIG.load(deps.storage)?;
+    Ok(fee_config)
+}
+
+fn query_pending_fee_config(deps: Deps, _env: Env) -> StdResult<Option<PendingFeeConfig>> {
+    let pending_fee_config = PENDING_FEE_CONFIG.may_load(deps.storage)?;
+    Ok(pending_fee_config)
+}
+
+fn query_pause_info(deps: Deps, _env: Env) -> StdResult<PauseInfo> {
+    let pause_info = PAUSE_INFO.load(deps.storage)?;
+    Ok(pause_info)
+}
+
+fn query_fee_discount(deps: Deps, _env: Env) -> StdResult<Option<FeeDiscount>> {
+    let fee_discount = FEE_DISCOUNT.may_load(deps.storage)?;
+    Ok(fee_discount)
+}
+
+fn query_effective_fee(deps: Deps, _env: Env, address: String) -> StdResult<EffectiveFeeResponse> {
+    let fee_config = FEE_CONFIG.load(deps.storage)?;
+    let (percentage, tier) = get_effective_fee(deps, &fee_config, &address)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    Ok(EffectiveFeeResponse { percentage, tier })
+}
+
+fn query_hooks(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<HooksResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let hooks = HOOKS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<Addr>>>()?;
+    Ok(HooksResponse { hooks })
+}
+
+fn query_hook_gas_limit(deps: Deps, _env: Env) -> StdResult<HookGasLimitResponse> {
+    let gas_limit = HOOK_GAS_LIMIT
+        .may_load(deps.storage)?
+        .unwrap_or(DEFAULT_HOOK_GAS_LIMIT);
+    Ok(HookGasLimitResponse { gas_limit })
+}
+
+fn query_deposits(deps: Deps, _env: Env, address: String) -> StdResult<DepositsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let deposits = DEPOSITS
+        .prefix(&address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(Coin { denom, amount })
+        })
+        .collect::<StdResult<Vec<Coin>>>()?;
+    Ok(DepositsResponse { deposits })
+}
+
+fn query_is_nonce_used(
+    deps: Deps,
+    _env: Env,
+    maker: String,
+    nonce: u64,
+) -> StdResult<IsNonceUsedResponse> {
+    let maker = deps.api.addr_validate(&maker)?;
+    let used = USED_NONCES.has(deps.storage, (&maker, nonce));
+    Ok(IsNonceUsedResponse { used })
+}
+
+fn query_swap_status(deps: Deps, _env: Env, swap_id: u64) -> StdResult<Option<RegistryStatus>> {
+    SWAP_STATUSES.may_load(deps.storage, swap_id)
+}
+
+fn query_sweep_bounty(deps: Deps, _env: Env) -> StdResult<SweepBountyResponse> {
+    let bounty = SWEEP_BOUNTY.may_load(deps.storage)?;
+    let pool = SWEEP_POOL
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(Coin { denom, amount })
+        })
+        .collect::<StdResult<Vec<Coin>>>()?;
+    Ok(SweepBountyResponse { bounty, pool })
+}
+
+fn query_creation_deposit_config(
+    deps: Deps,
+    _env: Env,
+) -> StdResult<Option<CreationDepositConfig>> {
+    CREATION_DEPOSIT_CONFIG.may_load(deps.storage)
+}
+
+fn query_creation_deposit(
+    deps: Deps,
+    _env: Env,
+    swap_id: u64,
+) -> StdResult<Option<CreationDeposit>> {
+    CREATION_DEPOSITS.may_load(deps.storage, swap_id)
+}
+
+fn query_is_registered_swap(
+    deps: Deps,
+    _env: Env,
+    address: String,
+) -> StdResult<IsRegisteredSwapResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let swap_id = SWAP_IDS.may_load(deps.storage, &address)?;
+    Ok(IsRegisteredSwapResponse {
+        registered: swap_id.is_some(),
+        swap_id,
+    })
+}
+
+fn query_predict_swap_address(
+    deps: Deps,
+    env: Env,
+    maker: String,
+    nonce: Option<u64>,
+    code_id: Option<u64>,
+) -> StdResult<PredictSwapAddressResponse> {
+    let maker = deps.api.addr_validate(&maker)?;
+    let nonce = match nonce {
+        Some(nonce) => nonce,
+        None => SWAP_NONCES
+            .may_load(deps.storage, &maker)?
+            .unwrap_or_default(),
+    };
+    let code_id = match code_id {
+        Some(code_id) => code_id,
+        None => CONFIG.load(deps.storage)?.cosmoswap_code_id,
+    };
+
+    let address = predict_swap_address(deps, &env, code_id, &swap_salt(&maker, nonce))?;
+    Ok(PredictSwapAddressResponse { address, nonce })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        INSTANTIATE_REPLY_ID => reply_instantiate(deps, env, msg),
+        HOOK_REPLY_ID => reply_hook(msg),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+/// Hook errors are swallowed so the swap settles anyway
+fn reply_hook(msg: Reply) -> Result<Response, ContractError> {
+    let error = msg.result.into_result().err().unwrap_or_default();
+    Ok(Response::new()
+        .add_attribute("action", "hook_failed")
+        .add_attribute("error", error))
+}
+
+fn reply_instantiate(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let reply_data =
+        parse_reply_instantiate_data(msg).map_err(|_| ContractError::SwapInstantiateError {})?;
+
+    let pending = PENDING_INSTANTIATE.load(deps.storage)?;
+    PENDING_INSTANTIATE.remove(deps.storage);
+
+    // Instantiated address needs to be the predicted one
+    if reply_data.contract_address != pending.address {
+        return Err(ContractError::SwapInstantiateError {});
+    }
+
+    // Make sure the whitelisted code id is the expected swap contract
+    let code_id_info = CODE_IDS.load(deps.storage, pending.code_id)?;
+    let contract_version = CONTRACT_INFO.query(&deps.querier, pending.address)?;
+    if contract_version.contract != COSMOSWAP_CONTRACT_NAME
+        || contract_version.version != code_id_info.version
+    {
+        return Err(ContractError::InvalidSwapVersion {
+            got: format!("{}@{}", contract_version.contract, contract_version.version),
+            expected: format!("{}@{}", COSMOSWAP_CONTRACT_NAME, code_id_info.version),
+        });
+    }
+
+    Ok(Response::new().add_attribute("action", "cosmoswap_instantiate_reply"))
 }
@@ -1,5 +1,6 @@
+use cosmoswap_packages::fees::FeeError;
 use cosmoswap_packages::funds::FundsError;
-use cosmwasm_std::StdError;
+use cosmwasm_std::{OverflowError, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -10,21 +11,120 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Role not found")]
+    RoleNotFound {},
+
+    #[error("No pending admin found")]
+    NoPendingAdmin {},
+
+    #[error("Admin proposal is expired")]
+    AdminProposalExpired {},
+
+    #[error("Hook is already registered")]
+    HookAlreadyRegistered {},
+
+    #[error("Hook is not registered")]
+    HookNotRegistered {},
+
+    #[error("Hook gas limit can not be zero")]
+    InvalidHookGasLimit {},
+
+    #[error("Swap creation is paused")]
+    CreationPaused {},
+
+    #[error("Settlement is paused")]
+    SettlementPaused {},
+
+    #[error("Offer is not valid")]
+    InvalidOffer {},
+
+    #[error("Offer is expired")]
+    OfferExpired {},
+
+    #[error("Offer signature is not valid")]
+    InvalidSignature {},
+
+    #[error("Nonce {nonce} is already used")]
+    NonceUsed { nonce: u64 },
+
+    #[error("Insufficient deposit")]
+    InsufficientDeposit {},
+
+    #[error("Creation deposit is not sent")]
+    MissingCreationDeposit {},
+
+    #[error("Creation deposit needs a non-zero amount and a forfeit percentage up to 100%")]
+    InvalidCreationDeposit {},
+
+    #[error("Native funds can not be sent for a cw20 coin1")]
+    UnexpectedFunds {},
+
+    #[error("Swap not found")]
+    SwapNotFound {},
+
     #[error("Error while instantiating swap contract")]
     SwapInstantiateError {},
 
+    #[error("Code id {code_id} is not allowed")]
+    CodeIdNotAllowed { code_id: u64 },
+
+    #[error("Default code id {code_id} can not be removed or disabled")]
+    CannotRemoveDefaultCodeId { code_id: u64 },
+
+    #[error("Swap contract reports {got}, expected {expected}")]
+    InvalidSwapVersion { got: String, expected: String },
+
     #[error("Denoms cannot be the same")]
     SameDenoms {},
 
     #[error("Users cannot be the same")]
     SameUsers {},
 
+    #[error("IBC recipients need a native coin, a channel, a receiver and a timeout")]
+    InvalidIbcRecipient {},
+
+    #[error("Arbiter must be a third party with a fee below 100% and no hash lock")]
+    InvalidArbiter {},
+
+    #[error("Confirmation window must be above zero and can not be used with a hash lock")]
+    InvalidConfirmationWindow {},
+
+    #[error("Dutch auctions need a start amount above coin2, a valid decay and no hash lock or confirmation window")]
+    InvalidPricing {},
+
+    #[error("Sweep bounty can not be zero")]
+    InvalidSweepBounty {},
+
+    #[error("No expired swaps to sweep")]
+    NothingToSweep {},
+
     #[error("Invalid cw20 address")]
     InvalidCw20Addr {},
 
     #[error("Invalid expiration time")]
     InvalidExpiration {},
 
+    #[error("Hash lock needs to be a sha256 hash")]
+    InvalidHashLock {},
+
+    #[error("Fee percentage is too high")]
+    InvalidFeePercentage {},
+
+    #[error("No pending fee config found")]
+    NoPendingFeeConfig {},
+
+    #[error("Fee config cannot be applied before the timelock ends")]
+    FeeConfigTimelocked {},
+
+    #[error("Invalid fee discount tiers")]
+    InvalidDiscountTiers {},
+
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
     #[error("{0}")]
     Funds(#[from] FundsError),
+
+    #[error("{0}")]
+    Fee(#[from] FeeError),
 }
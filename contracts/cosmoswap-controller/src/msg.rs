@@ -1,37 +1,156 @@
-use crate::state::Config;
-use cosmoswap_packages::types::{FeeInfo, SwapInfo};
+use crate::state::{
+    Config, CreationDeposit, CreationDepositConfig, DiscountSource, FeeDiscount, FeeDiscountTier,
+    PendingAdmin, PendingFeeConfig, RegistryStatus, Role,
+};
+use cosmoswap_packages::types::{
+    FeeBound, FeeDestination, FeeInfo, PauseInfo, Recipient, Rounding, SwapEvent, SwapInfo,
+};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Decimal;
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Coin, CosmosMsg, Decimal, HexBinary, StdResult, Uint128, WasmMsg,
+};
 use cw20::{Cw20ReceiveMsg, Expiration};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub cosmoswap_code_id: u64,
+    pub cosmoswap_version: String,
     pub fee_percentage: Decimal,
     pub fee_payment_address: String,
+    pub fee_rounding: Rounding,
+    pub fee_bounds: Vec<FeeBound>,
+    pub fee_destination: FeeDestination,
 }
 
 #[cw_serde]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     UpdateConfig {
         cosmoswap_code_id: u64,
     },
-    UpdateFeeConfig {
+    SetCodeId {
+        code_id: u64,
+        version: String,
+        enabled: bool,
+    },
+    RemoveCodeId {
+        code_id: u64,
+    },
+    ProposeNewAdmin {
+        address: String,
+        expiration: Expiration,
+    },
+    AcceptAdmin {},
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    RenounceAdmin {},
+    ProposeFeeConfig {
         fee_percentage: Decimal,
         fee_payment_address: String,
+        fee_rounding: Rounding,
+        fee_bounds: Vec<FeeBound>,
+        fee_destination: FeeDestination,
+    },
+    ApplyFeeConfig {},
+    CancelFeeConfig {},
+    UpdateFeeDiscount {
+        source: DiscountSource,
+        tiers: Vec<FeeDiscountTier>,
+    },
+    RemoveFeeDiscount {},
+    SetPause {
+        creation: bool,
+        settlement: bool,
+    },
+    /// Sets or removes the anti-spam deposit locked on swap creation, cw20 swaps take it from
+    /// the Deposit balance of user1
+    SetCreationDeposit {
+        creation_deposit: Option<CreationDepositConfig>,
+    },
+    /// Sets or removes the bounty paid for sweeping an expired swap
+    SetSweepBounty {
+        bounty: Option<Coin>,
+    },
+    /// Adds the sent native coins to the pool paying sweep bounties
+    FundSweepPool {},
+    /// Returns coin1 of expired swaps to user1 and pays the sender a bounty for each. Visits at
+    /// most limit expired swaps and no unexpired ones, the ones that can not be swept are dropped
+    /// from the index.
+    SweepExpired {
+        limit: Option<u32>,
+    },
+    AddHook {
+        address: String,
+    },
+    RemoveHook {
+        address: String,
+    },
+    /// Sets the gas each hook subscriber can use per notification
+    SetHookGasLimit {
+        gas_limit: u64,
     },
     CreateSwap {
         swap_info: SwapInfo,
         expiration: Expiration,
+        code_id: Option<u64>,
+        /// Creates a hashed time-locked swap when set
+        hash_lock: Option<HexBinary>,
+    },
+    /// Called by registered swaps to notify the hook subscribers
+    NotifySwap {
+        event: SwapEvent,
+    },
+    /// Deposits native coins used to settle signed offers of the sender
+    Deposit {},
+    Withdraw {
+        amount: Coin,
+    },
+    /// Settles an offer signed by user1 with the coin2 sent by user2
+    TakeOffer {
+        offer: Offer,
+        signature: Binary,
+        pubkey: Binary,
+    },
+    /// Invalidates a signed offer nonce of the sender
+    CancelNonce {
+        nonce: u64,
     },
     Receive(Cw20ReceiveMsg),
 }
 
+// Swap offer signed off-chain by user1 with an ADR-036 sign doc
+#[cw_serde]
+pub struct Offer {
+    // Controller the offer can be taken on
+    pub controller: String,
+    pub swap_info: SwapInfo,
+    pub expiration: Expiration,
+    pub nonce: u64,
+}
+
 #[cw_serde]
 pub enum ReceiveMsg {
     CreateSwap {
         swap_info: SwapInfo,
         expiration: Expiration,
+        code_id: Option<u64>,
+        hash_lock: Option<HexBinary>,
+    },
+    TakeOffer {
+        offer: Offer,
+        signature: Binary,
+        pubkey: Binary,
+    },
+    /// Forwards the tokens to the swap and accepts it for the sender
+    AcceptSwap {
+        swap_id: u64,
+        recipient: Option<Recipient>,
     },
 }
 
@@ -40,6 +159,212 @@ pub enum ReceiveMsg {
 pub enum QueryMsg {
     #[returns(Config)]
     Config {},
+    #[returns(CodeIdsResponse)]
+    CodeIds {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(Option<PendingAdmin>)]
+    PendingAdmin {},
+    #[returns(RoleHoldersResponse)]
+    RoleHolders {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     #[returns(FeeInfo)]
     FeeConfig {},
+    #[returns(Option<PendingFeeConfig>)]
+    PendingFeeConfig {},
+    #[returns(PauseInfo)]
+    PauseInfo {},
+    #[returns(Option<FeeDiscount>)]
+    FeeDiscount {},
+    #[returns(EffectiveFeeResponse)]
+    EffectiveFee { address: String },
+    #[returns(HooksResponse)]
+    Hooks {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(HookGasLimitResponse)]
+    HookGasLimit {},
+    #[returns(DepositsResponse)]
+    Deposits { address: String },
+    #[returns(IsNonceUsedResponse)]
+    IsNonceUsed { maker: String, nonce: u64 },
+    #[returns(IsRegisteredSwapResponse)]
+    IsRegisteredSwap { address: String },
+    #[returns(Option<RegistryStatus>)]
+    SwapStatus { swap_id: u64 },
+    #[returns(SweepBountyResponse)]
+    SweepBounty {},
+    #[returns(Option<CreationDepositConfig>)]
+    CreationDepositConfig {},
+    #[returns(Option<CreationDeposit>)]
+    CreationDeposit { swap_id: u64 },
+    /// Nonce defaults to the next nonce of the maker and code id to the default one
+    #[returns(PredictSwapAddressResponse)]
+    PredictSwapAddress {
+        maker: String,
+        nonce: Option<u64>,
+        code_id: Option<u64>,
+    },
+}
+
+#[cw_serde]
+pub struct CodeIdResponse {
+    pub code_id: u64,
+    pub version: String,
+    pub enabled: bool,
+}
+
+#[cw_serde]
+pub struct CodeIdsResponse {
+    pub code_ids: Vec<CodeIdResponse>,
+}
+
+#[cw_serde]
+pub struct RoleHoldersResponse {
+    pub holders: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct EffectiveFeeResponse {
+    pub percentage: Decimal,
+    pub tier: Option<u32>,
+}
+
+#[cw_serde]
+pub struct PredictSwapAddressResponse {
+    pub address: Addr,
+    pub nonce: u64,
+}
+
+#[cw_serde]
+pub struct HooksResponse {
+    pub hooks: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct HookGasLimitResponse {
+    pub gas_limit: u64,
+}
+
+#[cw_serde]
+pub struct DepositsResponse {
+    pub deposits: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct SweepBountyResponse {
+    pub bounty: Option<Coin>,
+    pub pool: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct IsNonceUsedResponse {
+    pub used: bool,
+}
+
+#[cw_serde]
+pub struct IsRegisteredSwapResponse {
+    pub registered: bool,
+    pub swap_id: Option<u64>,
+}
+
+#[cw_serde]
+pub enum SwapHookMsg {
+    Completed {
+        swap_id: u64,
+        swap_address: Addr,
+    },
+    Cancelled {
+        swap_id: u64,
+        swap_address: Addr,
+    },
+    Refunded {
+        swap_id: u64,
+        swap_address: Addr,
+    },
+    Resolved {
+        swap_id: u64,
+        swap_address: Addr,
+    },
+    Deposited {
+        swap_id: u64,
+        swap_address: Addr,
+    },
+    Withdrawn {
+        swap_id: u64,
+        swap_address: Addr,
+    },
+    // Signed offers are settled by the controller without a swap contract
+    OfferTaken {
+        maker: Addr,
+        taker: Addr,
+        nonce: u64,
+    },
+}
+
+impl SwapHookMsg {
+    pub fn new(event: SwapEvent, swap_id: u64, swap_address: Addr) -> Self {
+        match event {
+            SwapEvent::Completed => SwapHookMsg::Completed {
+                swap_id,
+                swap_address,
+            },
+            SwapEvent::Cancelled => SwapHookMsg::Cancelled {
+                swap_id,
+                swap_address,
+            },
+            SwapEvent::Refunded => SwapHookMsg::Refunded {
+                swap_id,
+                swap_address,
+            },
+            SwapEvent::Resolved => SwapHookMsg::Resolved {
+                swap_id,
+                swap_address,
+            },
+            SwapEvent::Deposited => SwapHookMsg::Deposited {
+                swap_id,
+                swap_address,
+            },
+            SwapEvent::Withdrawn => SwapHookMsg::Withdrawn {
+                swap_id,
+                swap_address,
+            },
+        }
+    }
+
+    /// Wraps the hook message in the execute message subscribers need to handle
+    pub fn into_cosmos_msg(self, contract_addr: impl Into<String>) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: to_json_binary(&SwapHookExecuteMsg::SwapHook(self))?,
+            funds: vec![],
+        }
+        .into())
+    }
+}
+
+// Execute message sent to hook subscribers
+#[cw_serde]
+pub enum SwapHookExecuteMsg {
+    SwapHook(SwapHookMsg),
+}
+
+// Query interface of cw20-stake contracts used for fee discounts
+#[cw_serde]
+pub enum StakingQueryMsg {
+    StakedBalanceAtHeight {
+        address: String,
+        height: Option<u64>,
+    },
+}
+
+#[cw_serde]
+pub struct StakedBalanceAtHeightResponse {
+    pub balance: Uint128,
+    pub height: u64,
 }
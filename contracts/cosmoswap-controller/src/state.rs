@@ -1,13 +1,158 @@
-use cosmoswap_packages::types::FeeInfo;
+use cosmoswap_packages::types::{FeeInfo, PauseInfo, SwapEvent};
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Addr;
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Coin, Decimal, Empty, Timestamp, Uint128};
+use cw20::Expiration;
+use cw_storage_plus::{Item, Map};
 
 #[cw_serde]
 pub struct Config {
-    pub admin: Addr,
+    // Admin is removed once renounced
+    pub admin: Option<Addr>,
+    // Default code id used when a swap does not select one
     pub cosmoswap_code_id: u64,
 }
 pub const CONFIG: Item<Config> = Item::new("config");
 
+#[cw_serde]
+pub struct CodeIdInfo {
+    // cw2 version the instantiated swap contract needs to report
+    pub version: String,
+    pub enabled: bool,
+}
+// Whitelisted cosmoswap code ids
+pub const CODE_IDS: Map<u64, CodeIdInfo> = Map::new("code_ids");
+
+#[cw_serde]
+pub struct PendingInstantiate {
+    pub code_id: u64,
+    pub address: Addr,
+}
+// Swap being instantiated, read back on reply
+pub const PENDING_INSTANTIATE: Item<PendingInstantiate> = Item::new("pending_instantiate");
+
+// Registry of the swap contracts instantiated by the controller
+pub const SWAP_COUNT: Item<u64> = Item::new("swap_count");
+pub const SWAPS: Map<u64, Addr> = Map::new("swaps");
+pub const SWAP_IDS: Map<&Addr, u64> = Map::new("swap_ids");
+
+#[cw_serde]
+pub enum RegistryStatus {
+    Open {},
+    Closed { event: SwapEvent },
+    // Returned to user1 by a keeper after expiration
+    Swept { keeper: Addr },
+}
+pub const SWAP_STATUSES: Map<u64, RegistryStatus> = Map::new("swap_statuses");
+// Expiration of the open swaps that can be swept once expired
+pub const EXPIRING_SWAPS: Map<u64, Expiration> = Map::new("expiring_swaps");
+// Open swaps ordered by the height they expire at, so sweeps only range over expired ones
+pub const EXPIRING_AT_HEIGHT: Map<(u64, u64), Empty> = Map::new("expiring_at_height");
+// Open swaps ordered by the time in nanoseconds they expire at
+pub const EXPIRING_AT_TIME: Map<(u64, u64), Empty> = Map::new("expiring_at_time");
+
+// Bounty paid to keepers for each swept swap
+pub const SWEEP_BOUNTY: Item<Coin> = Item::new("sweep_bounty");
+// Native coins funded to pay sweep bounties by denom
+pub const SWEEP_POOL: Map<&str, Uint128> = Map::new("sweep_pool");
+
+// Subscriber contracts notified on swap events
+pub const HOOKS: Map<&Addr, Empty> = Map::new("hooks");
+// Gas each subscriber can use before its notification is dropped
+pub const HOOK_GAS_LIMIT: Item<u64> = Item::new("hook_gas_limit");
+
+// Native coins deposited by makers to back their signed offers
+pub const DEPOSITS: Map<(&Addr, &str), Uint128> = Map::new("deposits");
+// Signed offer nonces of each maker that are taken or cancelled
+pub const USED_NONCES: Map<(&Addr, u64), Empty> = Map::new("used_nonces");
+
+#[cw_serde]
+pub struct CreationDepositConfig {
+    // Native coin makers lock when creating a swap
+    pub amount: Coin,
+    // Seconds a swap needs to be open before cancelling refunds the deposit
+    pub min_lifetime: u64,
+    // Share of the deposit paid to user2 when user1 cancels right after user2 deposited
+    pub forfeit_percentage: Decimal,
+    // Seconds after the deposit of user2 in which cancelling forfeits
+    pub forfeit_window: u64,
+}
+pub const CREATION_DEPOSIT_CONFIG: Item<CreationDepositConfig> =
+    Item::new("creation_deposit_config");
+
+#[cw_serde]
+pub struct CreationDeposit {
+    pub maker: Addr,
+    pub taker: Addr,
+    // Terms are fixed when the swap is created
+    pub terms: CreationDepositConfig,
+    pub created_at: Timestamp,
+    // Set while user2 has coin2 deposited on a two-phase swap
+    pub locked_at: Option<Timestamp>,
+}
+// Anti-spam deposits of open swaps by swap id
+pub const CREATION_DEPOSITS: Map<u64, CreationDeposit> = Map::new("creation_deposits");
+
+// Next instantiate2 salt nonce of each maker
+pub const SWAP_NONCES: Map<&Addr, u64> = Map::new("swap_nonces");
+
+#[cw_serde]
+pub struct PendingAdmin {
+    pub address: Addr,
+    pub expiration: Expiration,
+}
+pub const PENDING_ADMIN: Item<PendingAdmin> = Item::new("pending_admin");
+
+#[cw_serde]
+pub enum Role {
+    FeeManager,
+    CodeManager,
+    Pauser,
+    ListCurator,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::FeeManager => "fee_manager",
+            Role::CodeManager => "code_manager",
+            Role::Pauser => "pauser",
+            Role::ListCurator => "list_curator",
+        }
+    }
+}
+
+// Role name and holder address
+pub const ROLES: Map<(&str, &Addr), Empty> = Map::new("roles");
+
 pub const FEE_CONFIG: Item<FeeInfo> = Item::new("fee_config");
+
+pub const PAUSE_INFO: Item<PauseInfo> = Item::new("pause_info");
+
+#[cw_serde]
+pub struct PendingFeeConfig {
+    pub fee_info: FeeInfo,
+    pub apply_after: Timestamp,
+}
+pub const PENDING_FEE_CONFIG: Item<PendingFeeConfig> = Item::new("pending_fee_config");
+
+#[cw_serde]
+pub enum DiscountSource {
+    /// Discount is based on the cw20 balance of the user
+    Balance { cw20_address: String },
+    /// Discount is based on the amount staked by the user on a cw20-stake contract
+    Staked { staking_contract: String },
+}
+
+#[cw_serde]
+pub struct FeeDiscountTier {
+    pub min_amount: Uint128,
+    pub discount: Decimal,
+}
+
+#[cw_serde]
+pub struct FeeDiscount {
+    pub source: DiscountSource,
+    // Tiers are kept sorted by min_amount
+    pub tiers: Vec<FeeDiscountTier>,
+}
+pub const FEE_DISCOUNT: Item<FeeDiscount> = Item::new("fee_discount");
@@ -1,23 +1,41 @@
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::Config;
+use crate::contract::{execute, instantiate, reply};
+use crate::msg::{
+    CodeIdResponse, CodeIdsResponse, ExecuteMsg, HookGasLimitResponse, HooksResponse,
+    InstantiateMsg, QueryMsg, RoleHoldersResponse,
+};
+use crate::state::{
+    Config, CreationDepositConfig, DiscountSource, FeeDiscount, FeeDiscountTier, PendingAdmin,
+    PendingFeeConfig, Role, SWAP_IDS,
+};
 use crate::ContractError;
-use cosmoswap_packages::types::FeeInfo;
-use cosmwasm_std::Decimal;
-use cosmwasm_std::{Addr, Empty};
-use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+use cosmoswap_packages::fees::FeeError;
+use cosmoswap_packages::types::{
+    FeeBound, FeeDestination, FeeInfo, PauseInfo, Rounding, SwapEvent,
+};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{coin, Addr, Empty, Reply, ReplyOn, SubMsgResult};
+use cosmwasm_std::{Decimal, Uint128};
+use cw_multi_test::addons::{MockAddressGenerator, MockApiBech32};
+use cw_multi_test::{App, AppBuilder, BankKeeper, Contract, ContractWrapper, Executor, WasmKeeper};
 use std::str::FromStr;
 
-const ADMIN: &str = "juno..admin";
-const USER1: &str = "juno..user1";
-const USER2: &str = "juno..user2";
+const ADMIN: &str = "juno1335hded4gyzpt00fpz75mms4m7ck02wgw07yhw9grahj4dzg4yvq4sfful";
+const USER1: &str = "juno1pgzph9rze2j2xxavx4n7pdhxlkgsq7rak245x0vk7mgh3j4le6gq2ltl4u";
+const USER2: &str = "juno1vqjarrly327529599rcc4qhzvhwe34pp5uyy4gylvxe5zupeqx3se0zcpp";
 
-fn mock_app() -> App {
-    AppBuilder::new().build(|router, _, storage| {
-        router
-            .bank
-            .init_balance(storage, &Addr::unchecked(ADMIN), vec![])
-            .unwrap();
-    })
+type TestApp = App<BankKeeper, MockApiBech32>;
+
+// Bech32 addresses are needed for predicting instantiate2 addresses
+fn mock_app() -> TestApp {
+    AppBuilder::new()
+        .with_api(MockApiBech32::new("juno"))
+        .with_wasm(WasmKeeper::new().with_address_generator(MockAddressGenerator))
+        .build(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(ADMIN), vec![])
+                .unwrap();
+        })
 }
 
 fn cosmoswap_controller() -> Box<dyn Contract<Empty>> {
@@ -29,18 +47,22 @@ fn cosmoswap_controller() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
-fn proper_instantiate(app: &mut App, cosmoswap_code_id: u64, fee_percentage: &str) -> Addr {
+fn proper_instantiate(app: &mut TestApp, cosmoswap_code_id: u64, fee_percentage: &str) -> Addr {
     let code_id = app.store_code(cosmoswap_controller());
     let msg = InstantiateMsg {
         cosmoswap_code_id,
+        cosmoswap_version: "0.1.0".to_string(),
         fee_percentage: Decimal::from_str(fee_percentage).unwrap(),
         fee_payment_address: Addr::unchecked(ADMIN).to_string(),
+        fee_rounding: Rounding::Floor,
+        fee_bounds: vec![],
+        fee_destination: FeeDestination::Transfer,
     };
     app.instantiate_contract(
         code_id,
         Addr::unchecked(ADMIN),
         &msg,
-        &vec![],
+        &[],
         "cosmoswap-controller",
         None,
     )
@@ -57,8 +79,12 @@ mod instantiate {
         let code_id = app.store_code(cosmoswap_controller());
         let msg = InstantiateMsg {
             cosmoswap_code_id: 2,
+            cosmoswap_version: "0.1.0".to_string(),
             fee_percentage: Decimal::from_str("0.05").unwrap(),
             fee_payment_address: Addr::unchecked(ADMIN).to_string(),
+            fee_rounding: Rounding::Floor,
+            fee_bounds: vec![],
+            fee_destination: FeeDestination::Transfer,
         };
 
         let cosmoswap_controller_addr = app
@@ -66,13 +92,12 @@ mod instantiate {
                 code_id,
                 Addr::unchecked(ADMIN),
                 &msg,
-                &vec![],
+                &[],
                 "cosmoswap-controller",
                 None,
             )
             .unwrap();
 
-        assert_eq!(cosmoswap_controller_addr, "contract0");
         let res = app
             .wrap()
             .query_wasm_contract_info(cosmoswap_controller_addr)
@@ -93,6 +118,19 @@ mod execute {
             let mut app = mock_app();
             let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
 
+            let msg = ExecuteMsg::SetCodeId {
+                code_id: 2,
+                version: "0.2.0".to_string(),
+                enabled: true,
+            };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
             let msg = ExecuteMsg::UpdateConfig {
                 cosmoswap_code_id: 2,
             };
@@ -100,7 +138,7 @@ mod execute {
                 Addr::unchecked(ADMIN),
                 cosmoswap_controller_addr.clone(),
                 &msg,
-                &vec![],
+                &[],
             )
             .unwrap();
 
@@ -125,9 +163,149 @@ mod execute {
                     Addr::unchecked(USER1),
                     cosmoswap_controller_addr.clone(),
                     &msg,
-                    &vec![],
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+        }
+
+        #[test]
+        fn test_code_id_not_allowed() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::UpdateConfig {
+                cosmoswap_code_id: 2,
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::CodeIdNotAllowed { code_id: 2 }.to_string()
+            );
+        }
+    }
+
+    mod code_ids {
+        use super::*;
+
+        #[test]
+        fn test_happy_path() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::SetCodeId {
+                code_id: 2,
+                version: "0.2.0".to_string(),
+                enabled: false,
+            };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = QueryMsg::CodeIds {
+                start_after: None,
+                limit: None,
+            };
+            let res: CodeIdsResponse = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr.clone(), &msg)
+                .unwrap();
+            assert_eq!(
+                res.code_ids,
+                vec![
+                    CodeIdResponse {
+                        code_id: 1,
+                        version: "0.1.0".to_string(),
+                        enabled: true,
+                    },
+                    CodeIdResponse {
+                        code_id: 2,
+                        version: "0.2.0".to_string(),
+                        enabled: false,
+                    },
+                ]
+            );
+
+            let msg = ExecuteMsg::RemoveCodeId { code_id: 2 };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = QueryMsg::CodeIds {
+                start_after: None,
+                limit: None,
+            };
+            let res: CodeIdsResponse = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr, &msg)
+                .unwrap();
+            assert_eq!(res.code_ids.len(), 1);
+        }
+
+        #[test]
+        fn test_remove_default_code_id() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::RemoveCodeId { code_id: 1 };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
                 )
                 .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::CannotRemoveDefaultCodeId { code_id: 1 }.to_string()
+            );
+
+            let msg = ExecuteMsg::SetCodeId {
+                code_id: 1,
+                version: "0.1.0".to_string(),
+                enabled: false,
+            };
+            let err = app
+                .execute_contract(Addr::unchecked(ADMIN), cosmoswap_controller_addr, &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::CannotRemoveDefaultCodeId { code_id: 1 }.to_string()
+            );
+        }
+
+        #[test]
+        fn test_invalid_admin() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::SetCodeId {
+                code_id: 2,
+                version: "0.2.0".to_string(),
+                enabled: true,
+            };
+            let err = app
+                .execute_contract(Addr::unchecked(USER1), cosmoswap_controller_addr, &msg, &[])
+                .unwrap_err();
             assert_eq!(
                 err.source().unwrap().to_string(),
                 ContractError::Unauthorized {}.to_string()
@@ -135,7 +313,7 @@ mod execute {
         }
     }
 
-    mod update_fee_config {
+    mod propose_fee_config {
         use super::*;
 
         #[test]
@@ -143,25 +321,147 @@ mod execute {
             let mut app = mock_app();
             let cosmoswap_controller_addr = proper_instantiate(&mut app, 2, "0.05");
 
-            let msg = ExecuteMsg::UpdateFeeConfig {
+            let msg = ExecuteMsg::ProposeFeeConfig {
                 fee_percentage: Decimal::from_str("0.1").unwrap(),
                 fee_payment_address: USER2.to_string(),
+                fee_rounding: Rounding::Floor,
+                fee_bounds: vec![],
+                fee_destination: FeeDestination::Transfer,
             };
             app.execute_contract(
                 Addr::unchecked(ADMIN),
                 cosmoswap_controller_addr.clone(),
                 &msg,
-                &vec![],
+                &[],
+            )
+            .unwrap();
+
+            // Fee config cannot be applied before the timelock
+            let msg = ExecuteMsg::ApplyFeeConfig {};
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::FeeConfigTimelocked {}.to_string()
+            );
+
+            let msg = QueryMsg::PendingFeeConfig {};
+            let res: Option<PendingFeeConfig> = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr.clone(), &msg)
+                .unwrap();
+            let res = res.unwrap();
+            assert_eq!(res.fee_info.percentage, Decimal::from_str("0.1").unwrap());
+            assert_eq!(res.apply_after, app.block_info().time.plus_seconds(259_200));
+
+            let msg = QueryMsg::FeeConfig {};
+            let res: FeeInfo = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr.clone(), &msg)
+                .unwrap();
+            assert_eq!(res.percentage, Decimal::from_str("0.05").unwrap());
+
+            app.update_block(|block| block.time = block.time.plus_seconds(259_200));
+            let msg = ExecuteMsg::ApplyFeeConfig {};
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
             )
             .unwrap();
 
             let msg = QueryMsg::FeeConfig {};
             let res: FeeInfo = app
                 .wrap()
-                .query_wasm_smart(cosmoswap_controller_addr, &msg)
+                .query_wasm_smart(cosmoswap_controller_addr.clone(), &msg)
                 .unwrap();
             assert_eq!(res.percentage, Decimal::from_str("0.1").unwrap());
             assert_eq!(res.payment_address, USER2);
+
+            let msg = QueryMsg::PendingFeeConfig {};
+            let res: Option<PendingFeeConfig> = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr, &msg)
+                .unwrap();
+            assert_eq!(res, None);
+        }
+
+        #[test]
+        fn test_cancel() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 2, "0.05");
+
+            let msg = ExecuteMsg::ProposeFeeConfig {
+                fee_percentage: Decimal::from_str("0.1").unwrap(),
+                fee_payment_address: USER2.to_string(),
+                fee_rounding: Rounding::Floor,
+                fee_bounds: vec![],
+                fee_destination: FeeDestination::Transfer,
+            };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = ExecuteMsg::CancelFeeConfig {};
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            app.update_block(|block| block.time = block.time.plus_seconds(259_200));
+            let msg = ExecuteMsg::ApplyFeeConfig {};
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::NoPendingFeeConfig {}.to_string()
+            );
+        }
+
+        #[test]
+        fn test_invalid_fee_percentage() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 2, "0.05");
+
+            let msg = ExecuteMsg::ProposeFeeConfig {
+                fee_percentage: Decimal::from_str("1.5").unwrap(),
+                fee_payment_address: USER2.to_string(),
+                fee_rounding: Rounding::Floor,
+                fee_bounds: vec![],
+                fee_destination: FeeDestination::Transfer,
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::InvalidFeePercentage {}.to_string()
+            );
         }
 
         #[test]
@@ -169,16 +469,140 @@ mod execute {
             let mut app = mock_app();
             let cosmoswap_controller_addr = proper_instantiate(&mut app, 2, "0.05");
 
-            let msg = ExecuteMsg::UpdateFeeConfig {
+            let msg = ExecuteMsg::ProposeFeeConfig {
+                fee_percentage: Decimal::from_str("0.1").unwrap(),
+                fee_payment_address: USER2.to_string(),
+                fee_rounding: Rounding::Floor,
+                fee_bounds: vec![],
+                fee_destination: FeeDestination::Transfer,
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+        }
+
+        #[test]
+        fn test_invalid_fee_bounds() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 2, "0.05");
+
+            let msg = ExecuteMsg::ProposeFeeConfig {
                 fee_percentage: Decimal::from_str("0.1").unwrap(),
                 fee_payment_address: USER2.to_string(),
+                fee_rounding: Rounding::Floor,
+                fee_bounds: vec![FeeBound {
+                    asset: "denom1".to_string(),
+                    min_fee: Some(Uint128::new(100)),
+                    max_fee: Some(Uint128::new(10)),
+                }],
+                fee_destination: FeeDestination::Transfer,
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                FeeError::InvalidFeeBounds {
+                    asset: "denom1".to_string()
+                }
+                .to_string()
+            );
+        }
+    }
+
+    mod update_fee_discount {
+        use super::*;
+
+        #[test]
+        fn test_happy_path() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 2, "0.05");
+
+            let msg = ExecuteMsg::UpdateFeeDiscount {
+                source: DiscountSource::Balance {
+                    cw20_address: "juno183rfa8tvtp6ax7jr7dfaf7ywv870sykxdmhrg46xtfqtpkjp20sqxqdele"
+                        .to_string(),
+                },
+                tiers: vec![
+                    FeeDiscountTier {
+                        min_amount: Uint128::new(10_000),
+                        discount: Decimal::from_str("0.5").unwrap(),
+                    },
+                    FeeDiscountTier {
+                        min_amount: Uint128::new(1_000),
+                        discount: Decimal::from_str("0.1").unwrap(),
+                    },
+                ],
+            };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = QueryMsg::FeeDiscount {};
+            let res: Option<FeeDiscount> = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr.clone(), &msg)
+                .unwrap();
+            let res = res.unwrap();
+            assert_eq!(res.tiers[0].min_amount, Uint128::new(1_000));
+            assert_eq!(res.tiers[1].min_amount, Uint128::new(10_000));
+
+            let msg = ExecuteMsg::RemoveFeeDiscount {};
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = QueryMsg::FeeDiscount {};
+            let res: Option<FeeDiscount> = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr, &msg)
+                .unwrap();
+            assert_eq!(res, None);
+        }
+
+        #[test]
+        fn test_invalid_admin() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 2, "0.05");
+
+            let msg = ExecuteMsg::UpdateFeeDiscount {
+                source: DiscountSource::Balance {
+                    cw20_address: "juno183rfa8tvtp6ax7jr7dfaf7ywv870sykxdmhrg46xtfqtpkjp20sqxqdele"
+                        .to_string(),
+                },
+                tiers: vec![FeeDiscountTier {
+                    min_amount: Uint128::new(1_000),
+                    discount: Decimal::from_str("0.1").unwrap(),
+                }],
             };
             let err = app
                 .execute_contract(
                     Addr::unchecked(USER1),
                     cosmoswap_controller_addr.clone(),
                     &msg,
-                    &vec![],
+                    &[],
                 )
                 .unwrap_err();
             assert_eq!(
@@ -186,5 +610,647 @@ mod execute {
                 ContractError::Unauthorized {}.to_string()
             );
         }
+
+        #[test]
+        fn test_invalid_tiers() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 2, "0.05");
+
+            let msg = ExecuteMsg::UpdateFeeDiscount {
+                source: DiscountSource::Balance {
+                    cw20_address: "juno183rfa8tvtp6ax7jr7dfaf7ywv870sykxdmhrg46xtfqtpkjp20sqxqdele"
+                        .to_string(),
+                },
+                tiers: vec![FeeDiscountTier {
+                    min_amount: Uint128::new(1_000),
+                    discount: Decimal::from_str("1.5").unwrap(),
+                }],
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::InvalidDiscountTiers {}.to_string()
+            );
+        }
+    }
+
+    mod admin {
+        use super::*;
+        use cw20::Expiration;
+
+        #[test]
+        fn test_happy_path() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::ProposeNewAdmin {
+                address: USER1.to_string(),
+                expiration: Expiration::Never {},
+            };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = QueryMsg::PendingAdmin {};
+            let res: Option<PendingAdmin> = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr.clone(), &msg)
+                .unwrap();
+            assert_eq!(res.unwrap().address, USER1);
+
+            let msg = ExecuteMsg::AcceptAdmin {};
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = QueryMsg::Config {};
+            let res: Config = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr.clone(), &msg)
+                .unwrap();
+            assert_eq!(res.admin, Some(Addr::unchecked(USER1)));
+
+            // Old admin has no permissions anymore
+            let msg = ExecuteMsg::UpdateConfig {
+                cosmoswap_code_id: 2,
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+        }
+
+        #[test]
+        fn test_expired_proposal() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::ProposeNewAdmin {
+                address: USER1.to_string(),
+                expiration: Expiration::AtTime(app.block_info().time.plus_seconds(10)),
+            };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            app.update_block(|block| block.time = block.time.plus_seconds(10));
+            let msg = ExecuteMsg::AcceptAdmin {};
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::AdminProposalExpired {}.to_string()
+            );
+        }
+
+        #[test]
+        fn test_renounce() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::ProposeNewAdmin {
+                address: USER1.to_string(),
+                expiration: Expiration::Never {},
+            };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = ExecuteMsg::RenounceAdmin {};
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = QueryMsg::Config {};
+            let res: Config = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr.clone(), &msg)
+                .unwrap();
+            assert_eq!(res.admin, None);
+
+            // Pending admin is removed as well
+            let msg = ExecuteMsg::AcceptAdmin {};
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::NoPendingAdmin {}.to_string()
+            );
+        }
+    }
+
+    mod roles {
+        use super::*;
+
+        #[test]
+        fn test_happy_path() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::GrantRole {
+                role: Role::FeeManager,
+                address: USER1.to_string(),
+            };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = QueryMsg::RoleHolders {
+                role: Role::FeeManager,
+                start_after: None,
+                limit: None,
+            };
+            let res: RoleHoldersResponse = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr.clone(), &msg)
+                .unwrap();
+            assert_eq!(res.holders, vec![Addr::unchecked(USER1)]);
+
+            // Fee manager can propose fee configs
+            let msg = ExecuteMsg::ProposeFeeConfig {
+                fee_percentage: Decimal::from_str("0.1").unwrap(),
+                fee_payment_address: USER2.to_string(),
+                fee_rounding: Rounding::Floor,
+                fee_bounds: vec![],
+                fee_destination: FeeDestination::Transfer,
+            };
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            // Fee manager can set the creation deposit
+            let msg = ExecuteMsg::SetCreationDeposit {
+                creation_deposit: Some(CreationDepositConfig {
+                    amount: coin(100, "denom1"),
+                    min_lifetime: 10,
+                    forfeit_percentage: Decimal::percent(50),
+                    forfeit_window: 60,
+                }),
+            };
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            // Fee manager can set the sweep bounty
+            let msg = ExecuteMsg::SetSweepBounty {
+                bounty: Some(coin(10, "denom1")),
+            };
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            // Fee manager cannot update code ids
+            let msg = ExecuteMsg::UpdateConfig {
+                cosmoswap_code_id: 2,
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            let msg = ExecuteMsg::RevokeRole {
+                role: Role::FeeManager,
+                address: USER1.to_string(),
+            };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = ExecuteMsg::CancelFeeConfig {};
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            let msg = QueryMsg::RoleHolders {
+                role: Role::FeeManager,
+                start_after: None,
+                limit: None,
+            };
+            let res: RoleHoldersResponse = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr, &msg)
+                .unwrap();
+            assert!(res.holders.is_empty());
+        }
+
+        #[test]
+        fn test_invalid_admin() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::GrantRole {
+                role: Role::CodeManager,
+                address: USER1.to_string(),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            let msg = ExecuteMsg::RevokeRole {
+                role: Role::CodeManager,
+                address: USER1.to_string(),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::RoleNotFound {}.to_string()
+            );
+        }
+    }
+
+    mod set_pause {
+        use super::*;
+
+        #[test]
+        fn test_happy_path() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::GrantRole {
+                role: Role::Pauser,
+                address: USER1.to_string(),
+            };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = ExecuteMsg::SetPause {
+                creation: true,
+                settlement: false,
+            };
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = QueryMsg::PauseInfo {};
+            let res: PauseInfo = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr, &msg)
+                .unwrap();
+            assert!(res.creation);
+            assert!(!res.settlement);
+        }
+
+        #[test]
+        fn test_invalid_pauser() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::SetPause {
+                creation: true,
+                settlement: true,
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+        }
+    }
+
+    mod hooks {
+        use super::*;
+
+        #[test]
+        fn test_happy_path() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::GrantRole {
+                role: Role::ListCurator,
+                address: USER1.to_string(),
+            };
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let msg = ExecuteMsg::AddHook {
+                address: USER2.to_string(),
+            };
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::HookAlreadyRegistered {}.to_string()
+            );
+
+            let msg = QueryMsg::Hooks {
+                start_after: None,
+                limit: None,
+            };
+            let res: HooksResponse = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr.clone(), &msg)
+                .unwrap();
+            assert_eq!(res.hooks, vec![Addr::unchecked(USER2)]);
+
+            let msg = ExecuteMsg::RemoveHook {
+                address: USER2.to_string(),
+            };
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::HookNotRegistered {}.to_string()
+            );
+
+            let msg = QueryMsg::Hooks {
+                start_after: None,
+                limit: None,
+            };
+            let res: HooksResponse = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr, &msg)
+                .unwrap();
+            assert!(res.hooks.is_empty());
+        }
+
+        #[test]
+        fn test_set_gas_limit() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let res: HookGasLimitResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cosmoswap_controller_addr.clone(),
+                    &QueryMsg::HookGasLimit {},
+                )
+                .unwrap();
+            assert_eq!(res.gas_limit, 200_000);
+
+            let msg = ExecuteMsg::SetHookGasLimit { gas_limit: 0 };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::InvalidHookGasLimit {}.to_string()
+            );
+
+            let msg = ExecuteMsg::SetHookGasLimit { gas_limit: 50_000 };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            let res: HookGasLimitResponse = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_controller_addr, &QueryMsg::HookGasLimit {})
+                .unwrap();
+            assert_eq!(res.gas_limit, 50_000);
+        }
+
+        // Multi-test does not meter gas, the bounded submessages and the reply are checked directly
+        #[test]
+        fn test_gas_burning_hook() {
+            let mut deps = mock_dependencies();
+            let msg = InstantiateMsg {
+                cosmoswap_code_id: 1,
+                cosmoswap_version: "0.1.0".to_string(),
+                fee_percentage: Decimal::from_str("0.05").unwrap(),
+                fee_payment_address: ADMIN.to_string(),
+                fee_rounding: Rounding::Floor,
+                fee_bounds: vec![],
+                fee_destination: FeeDestination::Transfer,
+            };
+            instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+            let swap = Addr::unchecked("swap");
+            SWAP_IDS.save(deps.as_mut().storage, &swap, &1).unwrap();
+            for hook in ["hook1", "hook2"] {
+                let msg = ExecuteMsg::AddHook {
+                    address: hook.to_string(),
+                };
+                execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+            }
+            let msg = ExecuteMsg::SetHookGasLimit { gas_limit: 50_000 };
+            execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+            let msg = ExecuteMsg::NotifySwap {
+                event: SwapEvent::Completed,
+            };
+            let res = execute(deps.as_mut(), mock_env(), mock_info("swap", &[]), msg).unwrap();
+            assert_eq!(res.messages.len(), 2);
+            for msg in res.messages {
+                assert_eq!(msg.gas_limit, Some(50_000));
+                assert_eq!(msg.reply_on, ReplyOn::Error);
+            }
+
+            // Subscriber running out of its gas limit only fails its own submessage
+            let msg = Reply {
+                id: 2,
+                result: SubMsgResult::Err("out of gas in location: wasm contract".to_string()),
+            };
+            let res = reply(deps.as_mut(), mock_env(), msg).unwrap();
+            assert_eq!(res.attributes[0].value, "hook_failed");
+        }
+
+        #[test]
+        fn test_invalid_curator() {
+            let mut app = mock_app();
+            let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+            let msg = ExecuteMsg::AddHook {
+                address: USER2.to_string(),
+            };
+            let err = app
+                .execute_contract(Addr::unchecked(USER1), cosmoswap_controller_addr, &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+        }
     }
 }
@@ -1,37 +1,76 @@
+use cosmoswap::msg::{
+    ExecuteMsg as CosmoswapExecuteMsg, InstantiateMsg as CosmoswapInstantiateMsg,
+};
+use cosmoswap_controller::contract::adr036_sign_doc;
 use cosmoswap_controller::msg::ReceiveMsg;
-use cosmoswap_controller::msg::{ExecuteMsg, InstantiateMsg};
+use cosmoswap_controller::msg::{
+    DepositsResponse, EffectiveFeeResponse, ExecuteMsg, InstantiateMsg, IsNonceUsedResponse,
+    IsRegisteredSwapResponse, Offer, PredictSwapAddressResponse, QueryMsg, SwapHookExecuteMsg,
+    SwapHookMsg, SweepBountyResponse,
+};
+use cosmoswap_controller::state::{
+    CreationDeposit, CreationDepositConfig, DiscountSource, FeeDiscountTier, RegistryStatus,
+};
 use cosmoswap_controller::ContractError;
+use cosmoswap_packages::fees::FeeError;
 use cosmoswap_packages::funds::FundsError;
-use cosmoswap_packages::types::SwapCoin;
-use cosmoswap_packages::types::SwapInfo;
+use cosmoswap_packages::types::Rounding;
+use cosmoswap_packages::types::{Arbiter, DutchAuction, FeeBound, FeeDestination, PriceDecay};
+use cosmoswap_packages::types::{FeeInfo, PauseInfo, SwapEvent, SwapInfo};
+use cosmoswap_packages::types::{IbcRecipient, Recipient, SwapCoin};
 use cosmwasm_std::coin;
 use cosmwasm_std::Decimal;
-use cosmwasm_std::{to_binary, Uint128};
-use cosmwasm_std::{Addr, Empty};
-use cw20::Cw20Coin;
+use cosmwasm_std::{to_json_binary, Uint128, WasmMsg};
+use cosmwasm_std::{
+    Addr, Api, Binary, CanonicalAddr, Coin, Deps, DepsMut, Empty, Env, HexBinary, MessageInfo,
+    Response, StdError, StdResult,
+};
+use cw2::set_contract_version;
 use cw20::Expiration;
+use cw20::{BalanceResponse, Cw20Coin, Cw20QueryMsg};
 use cw20_base::msg::{ExecuteMsg as Cw20ExecuteMsg, InstantiateMsg as Cw20InstantiateMsg};
-use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+use cw_multi_test::addons::{MockAddressGenerator, MockApiBech32};
+use cw_multi_test::{
+    App, AppBuilder, AppResponse, BankKeeper, Contract, ContractWrapper, Executor, WasmKeeper,
+};
+use k256::ecdsa::signature::hazmat::PrehashSigner;
+use k256::ecdsa::{Signature, SigningKey};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
 use std::str::FromStr;
 
-const ADMIN: &str = "juno..admin";
-const USER1: &str = "juno..user1";
-const USER2: &str = "juno..user2";
+const ADMIN: &str = "juno1335hded4gyzpt00fpz75mms4m7ck02wgw07yhw9grahj4dzg4yvq4sfful";
+const USER1: &str = "juno1pgzph9rze2j2xxavx4n7pdhxlkgsq7rak245x0vk7mgh3j4le6gq2ltl4u";
+const USER2: &str = "juno1vqjarrly327529599rcc4qhzvhwe34pp5uyy4gylvxe5zupeqx3se0zcpp";
 const DENOM1: &str = "denom1";
 const DENOM2: &str = "denom2";
 const CW20_TICKER: &str = "teto";
 
-fn mock_app() -> App {
-    AppBuilder::new().build(|router, _, storage| {
-        router
-            .bank
-            .init_balance(
-                storage,
-                &Addr::unchecked(USER1),
-                vec![coin(1_000_000, DENOM1)],
-            )
-            .unwrap();
-    })
+type TestApp = App<BankKeeper, MockApiBech32>;
+
+// Bech32 addresses are needed for predicting instantiate2 addresses
+fn mock_app() -> TestApp {
+    AppBuilder::new()
+        .with_api(MockApiBech32::new("juno"))
+        .with_wasm(WasmKeeper::new().with_address_generator(MockAddressGenerator))
+        .build(|router, _, storage| {
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &Addr::unchecked(USER1),
+                    vec![coin(1_000_000, DENOM1)],
+                )
+                .unwrap();
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &Addr::unchecked(USER2),
+                    vec![coin(1_000_000, DENOM2)],
+                )
+                .unwrap();
+        })
 }
 
 fn cosmoswap_controller() -> Box<dyn Contract<Empty>> {
@@ -53,6 +92,47 @@ fn cosmoswap() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+// Subscriber that records the hook it received as an attribute
+fn hook_subscriber() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |_deps: DepsMut,
+         _env: Env,
+         _info: MessageInfo,
+         msg: SwapHookExecuteMsg|
+         -> StdResult<Response> {
+            let SwapHookExecuteMsg::SwapHook(hook) = msg;
+            let event = match hook {
+                SwapHookMsg::Completed { .. } => "completed",
+                SwapHookMsg::Cancelled { .. } => "cancelled",
+                SwapHookMsg::Refunded { .. } => "refunded",
+                SwapHookMsg::Resolved { .. } => "resolved",
+                SwapHookMsg::Deposited { .. } => "deposited",
+                SwapHookMsg::Withdrawn { .. } => "withdrawn",
+                SwapHookMsg::OfferTaken { .. } => "offer_taken",
+            };
+            Ok(Response::new().add_attribute("hook_event", event))
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::new())
+        },
+        |_deps: Deps, _env: Env, _msg: Empty| -> StdResult<Binary> { to_json_binary(&Empty {}) },
+    );
+    Box::new(contract)
+}
+
+fn failing_hook_subscriber() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Err(StdError::generic_err("hook failed"))
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::new())
+        },
+        |_deps: Deps, _env: Env, _msg: Empty| -> StdResult<Binary> { to_json_binary(&Empty {}) },
+    );
+    Box::new(contract)
+}
+
 fn cw20_contract() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
         cw20_base::contract::execute,
@@ -62,25 +142,29 @@ fn cw20_contract() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
-fn proper_instantiate(app: &mut App, cosmoswap_code_id: u64, fee_percentage: &str) -> Addr {
+fn proper_instantiate(app: &mut TestApp, cosmoswap_code_id: u64, fee_percentage: &str) -> Addr {
     let cosmoswap_controller_code_id = app.store_code(cosmoswap_controller());
     let msg = InstantiateMsg {
         cosmoswap_code_id,
+        cosmoswap_version: "0.1.0".to_string(),
         fee_percentage: Decimal::from_str(fee_percentage).unwrap(),
         fee_payment_address: Addr::unchecked(ADMIN).to_string(),
+        fee_rounding: Rounding::Floor,
+        fee_bounds: vec![],
+        fee_destination: FeeDestination::Transfer,
     };
     app.instantiate_contract(
         cosmoswap_controller_code_id,
         Addr::unchecked(ADMIN),
         &msg,
-        &vec![],
+        &[],
         "cosmoswap-controller",
         None,
     )
     .unwrap()
 }
 
-fn setup_cw20_token(app: &mut App) -> Addr {
+fn setup_cw20_token(app: &mut TestApp) -> Addr {
     let cw20_code_id = app.store_code(cw20_contract());
 
     // Create a new cw20 token
@@ -99,7 +183,7 @@ fn setup_cw20_token(app: &mut App) -> Addr {
         cw20_code_id,
         Addr::unchecked(ADMIN),
         &msg,
-        &vec![],
+        &[],
         "test cw20",
         None,
     )
@@ -135,36 +219,81 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 };
+                // Swap address is known before creating the swap
+                let res: PredictSwapAddressResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        cosmoswap_controller_addr.clone(),
+                        &QueryMsg::PredictSwapAddress {
+                            maker: USER1.to_string(),
+                            nonce: None,
+                            code_id: None,
+                        },
+                    )
+                    .unwrap();
+                assert_eq!(res.nonce, 0);
+                let swap_addr = res.address;
+
                 let msg = ExecuteMsg::CreateSwap {
                     swap_info: swap_info.clone(),
                     expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
                 };
                 app.execute_contract(
                     Addr::unchecked(USER1),
                     cosmoswap_controller_addr.clone(),
                     &msg,
-                    &vec![coin(1_000, DENOM1)],
+                    &[coin(1_000, DENOM1)],
                 )
                 .unwrap();
 
-                let res = app.wrap().query_wasm_contract_info("contract1").unwrap();
+                let res = app.wrap().query_wasm_contract_info(&swap_addr).unwrap();
                 assert_eq!(res.code_id, 1);
                 assert_eq!(res.creator, cosmoswap_controller_addr);
                 assert_eq!(res.admin, None);
 
+                let res: IsRegisteredSwapResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        cosmoswap_controller_addr.clone(),
+                        &QueryMsg::IsRegisteredSwap {
+                            address: swap_addr.to_string(),
+                        },
+                    )
+                    .unwrap();
+                assert!(res.registered);
+                assert_eq!(res.swap_id, Some(1));
+                let res: IsRegisteredSwapResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        cosmoswap_controller_addr.clone(),
+                        &QueryMsg::IsRegisteredSwap {
+                            address: cosmoswap_controller_addr.to_string(),
+                        },
+                    )
+                    .unwrap();
+                assert!(!res.registered);
+
                 // Creating swap with expiration
                 // Changing block time
                 // app.update_block(|block| block.time = block.time.plus_seconds(10));
                 let msg = ExecuteMsg::CreateSwap {
                     swap_info: swap_info.clone(),
                     expiration: Expiration::AtTime(app.block_info().time.plus_seconds(5)),
+                    code_id: None,
+                    hash_lock: None,
                 };
                 app.execute_contract(
                     Addr::unchecked(USER1),
                     cosmoswap_controller_addr.clone(),
                     &msg,
-                    &vec![coin(1_000, DENOM1)],
+                    &[coin(1_000, DENOM1)],
                 )
                 .unwrap();
 
@@ -176,12 +305,14 @@ mod execute {
                     expiration: Expiration::AtHeight(
                         app.block_info().height.checked_add(5).unwrap(),
                     ),
+                    code_id: None,
+                    hash_lock: None,
                 };
                 app.execute_contract(
                     Addr::unchecked(USER1),
                     cosmoswap_controller_addr.clone(),
                     &msg,
-                    &vec![coin(1_000, DENOM1)],
+                    &[coin(1_000, DENOM1)],
                 )
                 .unwrap();
             }
@@ -204,10 +335,16 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 };
                 let msg = ExecuteMsg::CreateSwap {
                     swap_info,
                     expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
                 };
 
                 let err = app
@@ -215,7 +352,7 @@ mod execute {
                         Addr::unchecked(USER1),
                         cosmoswap_controller_addr.clone(),
                         &msg,
-                        &vec![coin(1_000, DENOM1)],
+                        &[coin(1_000, DENOM1)],
                     )
                     .unwrap_err();
                 assert_eq!(
@@ -242,10 +379,16 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 };
                 let msg = ExecuteMsg::CreateSwap {
                     swap_info,
                     expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
                 };
 
                 let err = app
@@ -253,7 +396,7 @@ mod execute {
                         Addr::unchecked(USER1),
                         cosmoswap_controller_addr.clone(),
                         &msg,
-                        &vec![coin(1_000, DENOM1)],
+                        &[coin(1_000, DENOM1)],
                     )
                     .unwrap_err();
                 assert_eq!(
@@ -280,19 +423,25 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 };
 
                 app.update_block(|block| block.height = block.height.checked_add(10).unwrap());
                 let msg = ExecuteMsg::CreateSwap {
                     swap_info: swap_info.clone(),
                     expiration: Expiration::AtHeight(1),
+                    code_id: None,
+                    hash_lock: None,
                 };
                 let err = app
                     .execute_contract(
                         Addr::unchecked(USER1),
                         cosmoswap_controller_addr.clone(),
                         &msg,
-                        &vec![coin(1_000, DENOM1)],
+                        &[coin(1_000, DENOM1)],
                     )
                     .unwrap_err();
                 assert_eq!(
@@ -304,13 +453,15 @@ mod execute {
                 let msg = ExecuteMsg::CreateSwap {
                     swap_info,
                     expiration: Expiration::AtTime(app.block_info().time.minus_seconds(9)),
+                    code_id: None,
+                    hash_lock: None,
                 };
                 let err = app
                     .execute_contract(
                         Addr::unchecked(USER1),
                         cosmoswap_controller_addr.clone(),
                         &msg,
-                        &vec![coin(1_000, DENOM1)],
+                        &[coin(1_000, DENOM1)],
                     )
                     .unwrap_err();
                 assert_eq!(
@@ -318,222 +469,311 @@ mod execute {
                     ContractError::InvalidExpiration {}.to_string()
                 );
             }
-        }
-
-        mod cw20_token {
-            use cw20::{BalanceResponse, Cw20QueryMsg};
-
-            use super::*;
 
             #[test]
-            fn test_happy_path() {
+            fn test_swap_too_small() {
                 let mut app = mock_app();
                 let cosmoswap_code_id = app.store_code(cosmoswap());
-                let cosmoswap_controller_addr =
-                    proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
-
-                let cw20_addr = setup_cw20_token(&mut app);
+                let cosmoswap_controller_code_id = app.store_code(cosmoswap_controller());
+                let msg = InstantiateMsg {
+                    cosmoswap_code_id,
+                    cosmoswap_version: "0.1.0".to_string(),
+                    fee_percentage: Decimal::from_str("0.05").unwrap(),
+                    fee_payment_address: ADMIN.to_string(),
+                    fee_rounding: Rounding::Floor,
+                    fee_bounds: vec![FeeBound {
+                        asset: DENOM1.to_string(),
+                        min_fee: Some(Uint128::new(2_000)),
+                        max_fee: None,
+                    }],
+                    fee_destination: FeeDestination::Transfer,
+                };
+                let cosmoswap_controller_addr = app
+                    .instantiate_contract(
+                        cosmoswap_controller_code_id,
+                        Addr::unchecked(ADMIN),
+                        &msg,
+                        &[],
+                        "cosmoswap-controller",
+                        None,
+                    )
+                    .unwrap();
 
-                let swap_info = SwapInfo {
+                let swap_info = |amount: u128| SwapInfo {
                     user1: USER1.to_string(),
                     user2: USER2.to_string(),
                     coin1: SwapCoin {
-                        is_native: false,
-                        coin: coin(1_000, CW20_TICKER),
-                        cw20_address: Some(cw20_addr.to_string()),
+                        is_native: true,
+                        coin: coin(amount, DENOM1),
+                        cw20_address: None,
                     },
                     coin2: SwapCoin {
                         is_native: true,
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 };
-                let msg = ReceiveMsg::CreateSwap {
-                    swap_info,
+
+                // Fee needs to be below the swapped amount
+                for amount in [1_000, 2_000] {
+                    let msg = ExecuteMsg::CreateSwap {
+                        swap_info: swap_info(amount),
+                        expiration: Expiration::Never {},
+                        code_id: None,
+                        hash_lock: None,
+                    };
+                    let err = app
+                        .execute_contract(
+                            Addr::unchecked(USER1),
+                            cosmoswap_controller_addr.clone(),
+                            &msg,
+                            &[coin(amount, DENOM1)],
+                        )
+                        .unwrap_err();
+                    assert_eq!(
+                        err.source().unwrap().to_string(),
+                        FeeError::SwapTooSmall {
+                            amount: amount.to_string(),
+                            min_fee: "2000".to_string()
+                        }
+                        .to_string()
+                    );
+                }
+
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info: swap_info(2_001),
                     expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
                 };
                 app.execute_contract(
                     Addr::unchecked(USER1),
-                    cw20_addr.clone(),
-                    &Cw20ExecuteMsg::Send {
-                        contract: cosmoswap_controller_addr.to_string(),
-                        amount: Uint128::new(1_000),
-                        msg: to_binary(&msg).unwrap(),
-                    },
-                    &vec![],
+                    cosmoswap_controller_addr,
+                    &msg,
+                    &[coin(2_001, DENOM1)],
                 )
                 .unwrap();
+            }
 
-                let res = app.wrap().query_wasm_contract_info("contract2").unwrap();
-                assert_eq!(res.code_id, 1);
-                assert_eq!(res.creator, cosmoswap_controller_addr);
-                assert_eq!(res.admin, None);
+            #[test]
+            fn test_fee_discount() {
+                let mut app = mock_app();
+                let cosmoswap_code_id = app.store_code(cosmoswap());
+                let cosmoswap_controller_addr =
+                    proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
 
-                let res: BalanceResponse = app
+                let cw20_addr = setup_cw20_token(&mut app);
+
+                let msg = ExecuteMsg::UpdateFeeDiscount {
+                    source: DiscountSource::Balance {
+                        cw20_address: cw20_addr.to_string(),
+                    },
+                    tiers: vec![
+                        FeeDiscountTier {
+                            min_amount: Uint128::new(1_000),
+                            discount: Decimal::from_str("0.1").unwrap(),
+                        },
+                        FeeDiscountTier {
+                            min_amount: Uint128::new(1_000_000),
+                            discount: Decimal::from_str("0.5").unwrap(),
+                        },
+                    ],
+                };
+                app.execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap();
+
+                let res: EffectiveFeeResponse = app
                     .wrap()
                     .query_wasm_smart(
-                        cw20_addr,
-                        &Cw20QueryMsg::Balance {
-                            address: "contract2".to_string(),
+                        cosmoswap_controller_addr.clone(),
+                        &QueryMsg::EffectiveFee {
+                            address: USER2.to_string(),
                         },
                     )
                     .unwrap();
-                assert_eq!(res.balance, Uint128::new(1_000));
-            }
-
-            #[test]
-            fn test_invalid_user() {
-                let mut app = mock_app();
-                let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
-
-                let cw20_addr = setup_cw20_token(&mut app);
+                assert_eq!(res.percentage, Decimal::from_str("0.05").unwrap());
+                assert_eq!(res.tier, None);
 
                 let swap_info = SwapInfo {
-                    user1: ADMIN.to_string(),
+                    user1: USER1.to_string(),
                     user2: USER2.to_string(),
                     coin1: SwapCoin {
-                        is_native: false,
-                        coin: coin(1_000, CW20_TICKER),
-                        cw20_address: Some(cw20_addr.to_string()),
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
                     },
                     coin2: SwapCoin {
                         is_native: true,
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 };
-                let msg = ReceiveMsg::CreateSwap {
+                let msg = ExecuteMsg::CreateSwap {
                     swap_info,
                     expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
                 };
-
-                let err = app
+                let res = app
                     .execute_contract(
                         Addr::unchecked(USER1),
-                        cw20_addr.clone(),
-                        &Cw20ExecuteMsg::Send {
-                            contract: cosmoswap_controller_addr.to_string(),
-                            amount: Uint128::new(1_000),
-                            msg: to_binary(&msg).unwrap(),
-                        },
-                        &vec![],
+                        cosmoswap_controller_addr.clone(),
+                        &msg,
+                        &[coin(1_000, DENOM1)],
                     )
-                    .unwrap_err();
-                assert_eq!(
-                    err.source().unwrap().source().unwrap().to_string(),
-                    ContractError::Unauthorized {}.to_string()
-                );
+                    .unwrap();
+
+                let attrs: Vec<_> = res.events.iter().flat_map(|e| &e.attributes).collect();
+                assert!(attrs
+                    .iter()
+                    .any(|attr| attr.key == "fee_discount_tier" && attr.value == "1"));
+                assert!(attrs
+                    .iter()
+                    .any(|attr| attr.key == "fee_percentage" && attr.value == "0.025"));
             }
 
             #[test]
-            fn test_invalid_amount() {
+            fn test_invalid_hash_lock() {
                 let mut app = mock_app();
-                let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
-
-                let cw20_addr = setup_cw20_token(&mut app);
+                let cosmoswap_code_id = app.store_code(cosmoswap());
+                let cosmoswap_controller_addr =
+                    proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
 
                 let swap_info = SwapInfo {
                     user1: USER1.to_string(),
                     user2: USER2.to_string(),
                     coin1: SwapCoin {
-                        is_native: false,
-                        coin: coin(2_000, CW20_TICKER),
-                        cw20_address: Some(cw20_addr.to_string()),
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
                     },
                     coin2: SwapCoin {
                         is_native: true,
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 };
-                let msg = ReceiveMsg::CreateSwap {
+
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info: swap_info.clone(),
+                    expiration: Expiration::AtTime(app.block_info().time.plus_seconds(100)),
+                    code_id: None,
+                    hash_lock: Some(HexBinary::from(b"not a hash".as_slice())),
+                };
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cosmoswap_controller_addr.clone(),
+                        &msg,
+                        &[coin(1_000, DENOM1)],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    err.source().unwrap().to_string(),
+                    ContractError::InvalidHashLock {}.to_string()
+                );
+
+                // Funds would be locked forever without a timeout
+                let msg = ExecuteMsg::CreateSwap {
                     swap_info,
                     expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: Some(HexBinary::from([0u8; 32].as_slice())),
                 };
-
                 let err = app
                     .execute_contract(
                         Addr::unchecked(USER1),
-                        cw20_addr.clone(),
-                        &Cw20ExecuteMsg::Send {
-                            contract: cosmoswap_controller_addr.to_string(),
-                            amount: Uint128::new(1_000),
-                            msg: to_binary(&msg).unwrap(),
-                        },
-                        &vec![],
+                        cosmoswap_controller_addr,
+                        &msg,
+                        &[coin(1_000, DENOM1)],
                     )
                     .unwrap_err();
                 assert_eq!(
-                    err.source().unwrap().source().unwrap().to_string(),
-                    FundsError::InvalidFunds {
-                        got: "1000".to_string(),
-                        expected: "2000".to_string()
-                    }
-                    .to_string()
+                    err.source().unwrap().to_string(),
+                    ContractError::InvalidExpiration {}.to_string()
                 );
             }
 
             #[test]
-            fn test_invalid_denom() {
+            fn test_invalid_ibc_recipient() {
                 let mut app = mock_app();
-                let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
-
-                let cw20_addr = setup_cw20_token(&mut app);
+                let cosmoswap_code_id = app.store_code(cosmoswap());
+                let cosmoswap_controller_addr =
+                    proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
 
                 let swap_info = SwapInfo {
                     user1: USER1.to_string(),
                     user2: USER2.to_string(),
                     coin1: SwapCoin {
-                        is_native: false,
-                        coin: coin(1_000, "invalid"),
-                        cw20_address: Some(cw20_addr.to_string()),
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
                     },
                     coin2: SwapCoin {
                         is_native: true,
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: Some(Recipient::Ibc(IbcRecipient {
+                        channel_id: "channel-0".to_string(),
+                        receiver: "osmo1receiver".to_string(),
+                        timeout: 0,
+                    })),
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 };
-                let msg = ReceiveMsg::CreateSwap {
+
+                let msg = ExecuteMsg::CreateSwap {
                     swap_info,
                     expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
                 };
-
                 let err = app
                     .execute_contract(
                         Addr::unchecked(USER1),
-                        cw20_addr.clone(),
-                        &Cw20ExecuteMsg::Send {
-                            contract: cosmoswap_controller_addr.to_string(),
-                            amount: Uint128::new(1_000),
-                            msg: to_binary(&msg).unwrap(),
-                        },
-                        &vec![],
+                        cosmoswap_controller_addr,
+                        &msg,
+                        &[coin(1_000, DENOM1)],
                     )
                     .unwrap_err();
                 assert_eq!(
-                    err.source().unwrap().source().unwrap().to_string(),
-                    FundsError::InvalidDenom {
-                        got: "invalid".to_string(),
-                        expected: CW20_TICKER.to_string()
-                    }
-                    .to_string()
+                    err.source().unwrap().to_string(),
+                    ContractError::InvalidIbcRecipient {}.to_string()
                 );
             }
 
             #[test]
-            fn test_invalid_cw20_address() {
+            fn test_invalid_confirmation_window() {
                 let mut app = mock_app();
-                let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
-
-                let cw20_addr = setup_cw20_token(&mut app);
+                let cosmoswap_code_id = app.store_code(cosmoswap());
+                let cosmoswap_controller_addr =
+                    proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
 
-                let swap_info = SwapInfo {
+                let swap_info = |confirmation_window: Option<u64>| SwapInfo {
                     user1: USER1.to_string(),
                     user2: USER2.to_string(),
                     coin1: SwapCoin {
-                        is_native: false,
-                        coin: coin(1_000, CW20_TICKER),
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
                         cw20_address: None,
                     },
                     coin2: SwapCoin {
@@ -541,29 +781,2363 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window,
+                    pricing: None,
                 };
-                let msg = ReceiveMsg::CreateSwap {
-                    swap_info,
+
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info: swap_info(Some(0)),
                     expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
                 };
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cosmoswap_controller_addr.clone(),
+                        &msg,
+                        &[coin(1_000, DENOM1)],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    err.source().unwrap().to_string(),
+                    ContractError::InvalidConfirmationWindow {}.to_string()
+                );
 
+                // Hashed time-locked swaps settle on claim
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info: swap_info(Some(100)),
+                    expiration: Expiration::AtHeight(app.block_info().height + 100),
+                    code_id: None,
+                    hash_lock: Some(HexBinary::from(Sha256::digest(b"secret").to_vec())),
+                };
                 let err = app
                     .execute_contract(
                         Addr::unchecked(USER1),
-                        cw20_addr.clone(),
-                        &Cw20ExecuteMsg::Send {
-                            contract: cosmoswap_controller_addr.to_string(),
-                            amount: Uint128::new(1_000),
-                            msg: to_binary(&msg).unwrap(),
-                        },
-                        &vec![],
+                        cosmoswap_controller_addr,
+                        &msg,
+                        &[coin(1_000, DENOM1)],
                     )
                     .unwrap_err();
                 assert_eq!(
-                    err.source().unwrap().source().unwrap().to_string(),
-                    ContractError::InvalidCw20Addr {}.to_string()
+                    err.source().unwrap().to_string(),
+                    ContractError::InvalidConfirmationWindow {}.to_string()
                 );
             }
-        }
+
+            #[test]
+            fn test_invalid_pricing() {
+                let mut app = mock_app();
+                let cosmoswap_code_id = app.store_code(cosmoswap());
+                let cosmoswap_controller_addr =
+                    proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+                let start_time = app.block_info().time;
+                let swap_info = |start_amount: u128, decay: PriceDecay| SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: Some(DutchAuction {
+                        start_amount: Uint128::new(start_amount),
+                        start_time,
+                        end_time: start_time.plus_seconds(100),
+                        decay,
+                    }),
+                };
+
+                // Ask needs to decay towards coin2
+                let invalid_swap_infos = vec![
+                    swap_info(5_000, PriceDecay::Linear),
+                    swap_info(
+                        10_000,
+                        PriceDecay::Exponential {
+                            percentage: Decimal::percent(50),
+                            interval: 0,
+                        },
+                    ),
+                ];
+                for swap_info in invalid_swap_infos {
+                    let msg = ExecuteMsg::CreateSwap {
+                        swap_info,
+                        expiration: Expiration::Never {},
+                        code_id: None,
+                        hash_lock: None,
+                    };
+                    let err = app
+                        .execute_contract(
+                            Addr::unchecked(USER1),
+                            cosmoswap_controller_addr.clone(),
+                            &msg,
+                            &[coin(1_000, DENOM1)],
+                        )
+                        .unwrap_err();
+                    assert_eq!(
+                        err.source().unwrap().to_string(),
+                        ContractError::InvalidPricing {}.to_string()
+                    );
+                }
+
+                // Hashed time-locked swaps settle on claim
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info: swap_info(10_000, PriceDecay::Linear),
+                    expiration: Expiration::AtHeight(app.block_info().height + 100),
+                    code_id: None,
+                    hash_lock: Some(HexBinary::from(Sha256::digest(b"secret").to_vec())),
+                };
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cosmoswap_controller_addr.clone(),
+                        &msg,
+                        &[coin(1_000, DENOM1)],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    err.source().unwrap().to_string(),
+                    ContractError::InvalidPricing {}.to_string()
+                );
+
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info: swap_info(10_000, PriceDecay::Linear),
+                    expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
+                };
+                app.execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr,
+                    &msg,
+                    &[coin(1_000, DENOM1)],
+                )
+                .unwrap();
+            }
+
+            #[test]
+            fn test_invalid_arbiter() {
+                let mut app = mock_app();
+                let cosmoswap_code_id = app.store_code(cosmoswap());
+                let cosmoswap_controller_addr =
+                    proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+                let swap_info = |address: &str, fee_percentage: &str| SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: Some(Arbiter {
+                        address: address.to_string(),
+                        fee_percentage: Decimal::from_str(fee_percentage).unwrap(),
+                    }),
+                    confirmation_window: None,
+                    pricing: None,
+                };
+
+                // Arbiter can not be one of the users or take the whole coin
+                for swap_info in [swap_info(USER2, "0.01"), swap_info(ADMIN, "1")] {
+                    let msg = ExecuteMsg::CreateSwap {
+                        swap_info,
+                        expiration: Expiration::Never {},
+                        code_id: None,
+                        hash_lock: None,
+                    };
+                    let err = app
+                        .execute_contract(
+                            Addr::unchecked(USER1),
+                            cosmoswap_controller_addr.clone(),
+                            &msg,
+                            &[coin(1_000, DENOM1)],
+                        )
+                        .unwrap_err();
+                    assert_eq!(
+                        err.source().unwrap().to_string(),
+                        ContractError::InvalidArbiter {}.to_string()
+                    );
+                }
+
+                // Disputes are not supported for hashed time-locked swaps
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info: swap_info(ADMIN, "0.01"),
+                    expiration: Expiration::AtHeight(app.block_info().height + 100),
+                    code_id: None,
+                    hash_lock: Some(HexBinary::from(Sha256::digest(b"secret").to_vec())),
+                };
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cosmoswap_controller_addr.clone(),
+                        &msg,
+                        &[coin(1_000, DENOM1)],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    err.source().unwrap().to_string(),
+                    ContractError::InvalidArbiter {}.to_string()
+                );
+
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info: swap_info(ADMIN, "0.01"),
+                    expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
+                };
+                app.execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr,
+                    &msg,
+                    &[coin(1_000, DENOM1)],
+                )
+                .unwrap();
+            }
+
+            #[test]
+            fn test_code_id_selection() {
+                let mut app = mock_app();
+                let cosmoswap_code_id = app.store_code(cosmoswap());
+                let cosmoswap_controller_addr =
+                    proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+                let new_code_id = app.store_code(cosmoswap());
+                let wrong_code_id = app.store_code(cosmoswap());
+
+                let msg = ExecuteMsg::SetCodeId {
+                    code_id: new_code_id,
+                    version: "0.1.0".to_string(),
+                    enabled: false,
+                };
+                app.execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap();
+                let msg = ExecuteMsg::SetCodeId {
+                    code_id: wrong_code_id,
+                    version: "0.2.0".to_string(),
+                    enabled: true,
+                };
+                app.execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap();
+
+                let swap_info = SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                };
+
+                // Disabled code id cannot be used
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info: swap_info.clone(),
+                    expiration: Expiration::Never {},
+                    code_id: Some(new_code_id),
+                    hash_lock: None,
+                };
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cosmoswap_controller_addr.clone(),
+                        &msg,
+                        &[coin(1_000, DENOM1)],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    err.source().unwrap().to_string(),
+                    ContractError::CodeIdNotAllowed {
+                        code_id: new_code_id
+                    }
+                    .to_string()
+                );
+
+                // Instantiated contract needs to report the whitelisted version
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info: swap_info.clone(),
+                    expiration: Expiration::Never {},
+                    code_id: Some(wrong_code_id),
+                    hash_lock: None,
+                };
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cosmoswap_controller_addr.clone(),
+                        &msg,
+                        &[coin(1_000, DENOM1)],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    err.source().unwrap().to_string(),
+                    ContractError::InvalidSwapVersion {
+                        got: "crates.io:cosmoswap@0.1.0".to_string(),
+                        expected: "crates.io:cosmoswap@0.2.0".to_string(),
+                    }
+                    .to_string()
+                );
+
+                let msg = ExecuteMsg::SetCodeId {
+                    code_id: new_code_id,
+                    version: "0.1.0".to_string(),
+                    enabled: true,
+                };
+                app.execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap();
+
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info,
+                    expiration: Expiration::Never {},
+                    code_id: Some(new_code_id),
+                    hash_lock: None,
+                };
+                let res = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cosmoswap_controller_addr,
+                        &msg,
+                        &[coin(1_000, DENOM1)],
+                    )
+                    .unwrap();
+                let attrs: Vec<_> = res.events.iter().flat_map(|e| &e.attributes).collect();
+                assert!(attrs
+                    .iter()
+                    .any(|attr| attr.key == "code_id" && attr.value == new_code_id.to_string()));
+            }
+        }
+
+        mod cw20_token {
+            use cw20::{BalanceResponse, Cw20QueryMsg};
+
+            use super::*;
+
+            #[test]
+            fn test_happy_path() {
+                let mut app = mock_app();
+                let cosmoswap_code_id = app.store_code(cosmoswap());
+                let cosmoswap_controller_addr =
+                    proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+                let cw20_addr = setup_cw20_token(&mut app);
+
+                let swap_info = SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: false,
+                        coin: coin(1_000, CW20_TICKER),
+                        cw20_address: Some(cw20_addr.to_string()),
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                };
+                let res: PredictSwapAddressResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        cosmoswap_controller_addr.clone(),
+                        &QueryMsg::PredictSwapAddress {
+                            maker: USER1.to_string(),
+                            nonce: None,
+                            code_id: None,
+                        },
+                    )
+                    .unwrap();
+                let swap_addr = res.address;
+
+                let msg = ReceiveMsg::CreateSwap {
+                    swap_info,
+                    expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
+                };
+                app.execute_contract(
+                    Addr::unchecked(USER1),
+                    cw20_addr.clone(),
+                    &Cw20ExecuteMsg::Send {
+                        contract: cosmoswap_controller_addr.to_string(),
+                        amount: Uint128::new(1_000),
+                        msg: to_json_binary(&msg).unwrap(),
+                    },
+                    &[],
+                )
+                .unwrap();
+
+                let res = app.wrap().query_wasm_contract_info(&swap_addr).unwrap();
+                assert_eq!(res.code_id, 1);
+                assert_eq!(res.creator, cosmoswap_controller_addr);
+                assert_eq!(res.admin, None);
+
+                let res: BalanceResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        cw20_addr,
+                        &Cw20QueryMsg::Balance {
+                            address: swap_addr.to_string(),
+                        },
+                    )
+                    .unwrap();
+                assert_eq!(res.balance, Uint128::new(1_000));
+            }
+
+            #[test]
+            fn test_allowance() {
+                let mut app = mock_app();
+                let cosmoswap_code_id = app.store_code(cosmoswap());
+                let cosmoswap_controller_addr =
+                    proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+                let cw20_addr = setup_cw20_token(&mut app);
+
+                let swap_info = SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: false,
+                        coin: coin(1_000, CW20_TICKER),
+                        cw20_address: Some(cw20_addr.to_string()),
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                };
+                let msg = ExecuteMsg::CreateSwap {
+                    swap_info,
+                    expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
+                };
+
+                // Allowance is checked before the swap is created
+                app.execute_contract(
+                    Addr::unchecked(USER1),
+                    cw20_addr.clone(),
+                    &Cw20ExecuteMsg::IncreaseAllowance {
+                        spender: cosmoswap_controller_addr.to_string(),
+                        amount: Uint128::new(500),
+                        expires: None,
+                    },
+                    &[],
+                )
+                .unwrap();
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cosmoswap_controller_addr.clone(),
+                        &msg,
+                        &[],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    err.source().unwrap().to_string(),
+                    ContractError::Funds(FundsError::InsufficientAllowance {
+                        got: "500".to_string(),
+                        expected: "1000".to_string(),
+                    })
+                    .to_string()
+                );
+
+                app.execute_contract(
+                    Addr::unchecked(USER1),
+                    cw20_addr.clone(),
+                    &Cw20ExecuteMsg::IncreaseAllowance {
+                        spender: cosmoswap_controller_addr.to_string(),
+                        amount: Uint128::new(500),
+                        expires: None,
+                    },
+                    &[],
+                )
+                .unwrap();
+                let res: PredictSwapAddressResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        cosmoswap_controller_addr.clone(),
+                        &QueryMsg::PredictSwapAddress {
+                            maker: USER1.to_string(),
+                            nonce: None,
+                            code_id: None,
+                        },
+                    )
+                    .unwrap();
+                let swap_addr = res.address;
+                app.execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_controller_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap();
+
+                let res: BalanceResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        cw20_addr,
+                        &Cw20QueryMsg::Balance {
+                            address: swap_addr.to_string(),
+                        },
+                    )
+                    .unwrap();
+                assert_eq!(res.balance, Uint128::new(1_000));
+            }
+
+            #[test]
+            fn test_invalid_user() {
+                let mut app = mock_app();
+                let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+                let cw20_addr = setup_cw20_token(&mut app);
+
+                let swap_info = SwapInfo {
+                    user1: ADMIN.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: false,
+                        coin: coin(1_000, CW20_TICKER),
+                        cw20_address: Some(cw20_addr.to_string()),
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                };
+                let msg = ReceiveMsg::CreateSwap {
+                    swap_info,
+                    expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
+                };
+
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cw20_addr.clone(),
+                        &Cw20ExecuteMsg::Send {
+                            contract: cosmoswap_controller_addr.to_string(),
+                            amount: Uint128::new(1_000),
+                            msg: to_json_binary(&msg).unwrap(),
+                        },
+                        &[],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    err.source().unwrap().source().unwrap().to_string(),
+                    ContractError::Unauthorized {}.to_string()
+                );
+            }
+
+            #[test]
+            fn test_invalid_amount() {
+                let mut app = mock_app();
+                let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+                let cw20_addr = setup_cw20_token(&mut app);
+
+                let swap_info = SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: false,
+                        coin: coin(2_000, CW20_TICKER),
+                        cw20_address: Some(cw20_addr.to_string()),
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                };
+                let msg = ReceiveMsg::CreateSwap {
+                    swap_info,
+                    expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
+                };
+
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cw20_addr.clone(),
+                        &Cw20ExecuteMsg::Send {
+                            contract: cosmoswap_controller_addr.to_string(),
+                            amount: Uint128::new(1_000),
+                            msg: to_json_binary(&msg).unwrap(),
+                        },
+                        &[],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    err.source().unwrap().source().unwrap().to_string(),
+                    FundsError::InvalidFunds {
+                        got: "1000".to_string(),
+                        expected: "2000".to_string()
+                    }
+                    .to_string()
+                );
+            }
+
+            #[test]
+            fn test_invalid_denom() {
+                let mut app = mock_app();
+                let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+                let cw20_addr = setup_cw20_token(&mut app);
+
+                let swap_info = SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: false,
+                        coin: coin(1_000, "invalid"),
+                        cw20_address: Some(cw20_addr.to_string()),
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                };
+                let msg = ReceiveMsg::CreateSwap {
+                    swap_info,
+                    expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
+                };
+
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cw20_addr.clone(),
+                        &Cw20ExecuteMsg::Send {
+                            contract: cosmoswap_controller_addr.to_string(),
+                            amount: Uint128::new(1_000),
+                            msg: to_json_binary(&msg).unwrap(),
+                        },
+                        &[],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    err.source().unwrap().source().unwrap().to_string(),
+                    FundsError::InvalidDenom {
+                        got: "invalid".to_string(),
+                        expected: CW20_TICKER.to_string()
+                    }
+                    .to_string()
+                );
+            }
+
+            #[test]
+            fn test_invalid_cw20_address() {
+                let mut app = mock_app();
+                let cosmoswap_controller_addr = proper_instantiate(&mut app, 1, "0.05");
+
+                let cw20_addr = setup_cw20_token(&mut app);
+
+                let swap_info = SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: false,
+                        coin: coin(1_000, CW20_TICKER),
+                        cw20_address: None,
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                };
+                let msg = ReceiveMsg::CreateSwap {
+                    swap_info,
+                    expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
+                };
+
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(USER1),
+                        cw20_addr.clone(),
+                        &Cw20ExecuteMsg::Send {
+                            contract: cosmoswap_controller_addr.to_string(),
+                            amount: Uint128::new(1_000),
+                            msg: to_json_binary(&msg).unwrap(),
+                        },
+                        &[],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    err.source().unwrap().source().unwrap().to_string(),
+                    ContractError::InvalidCw20Addr {}.to_string()
+                );
+            }
+        }
+    }
+}
+
+mod registry {
+    use super::*;
+
+    // Contract reporting the controller cw2 name that forwards any wasm message
+    fn forged_controller() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            |_deps: DepsMut, _env: Env, _info: MessageInfo, msg: WasmMsg| -> StdResult<Response> {
+                Ok(Response::new().add_message(msg))
+            },
+            |deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+                set_contract_version(deps.storage, "crates.io:cosmoswap-controller", "0.1.0")?;
+                Ok(Response::new())
+            },
+            |_deps: Deps, _env: Env, _msg: Empty| -> StdResult<Binary> {
+                to_json_binary(&PauseInfo {
+                    creation: false,
+                    settlement: false,
+                })
+            },
+        );
+        Box::new(contract)
+    }
+
+    #[test]
+    fn test_forged_controller() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+        let forged_code_id = app.store_code(forged_controller());
+        let forged_addr = app
+            .instantiate_contract(
+                forged_code_id,
+                Addr::unchecked(ADMIN),
+                &Empty {},
+                &[],
+                "forged-controller",
+                None,
+            )
+            .unwrap();
+
+        // Swap contract accepts the forged controller with a fee config of its own
+        let msg = WasmMsg::Instantiate {
+            admin: None,
+            code_id: cosmoswap_code_id,
+            msg: to_json_binary(&CosmoswapInstantiateMsg {
+                fee_info: FeeInfo {
+                    percentage: Decimal::percent(90),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                swap_info: SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                },
+                expiration: Expiration::Never {},
+                hash_lock: None,
+            })
+            .unwrap(),
+            funds: vec![coin(1_000, DENOM1)],
+            label: "Cosmoswap Contract".to_string(),
+        };
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                forged_addr,
+                &msg,
+                &[coin(1_000, DENOM1)],
+            )
+            .unwrap();
+        let swap_addr = res
+            .events
+            .iter()
+            .filter(|e| e.ty == "instantiate")
+            .flat_map(|e| &e.attributes)
+            .find(|attr| attr.key == "_contract_address")
+            .unwrap();
+
+        // Only the registry of the controller tells the swap apart
+        let res: IsRegisteredSwapResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cosmoswap_controller_addr,
+                &QueryMsg::IsRegisteredSwap {
+                    address: swap_addr.value.clone(),
+                },
+            )
+            .unwrap();
+        assert!(!res.registered);
+        assert_eq!(res.swap_id, None);
+    }
+}
+
+mod hooks {
+    use super::*;
+
+    fn wasm_attribute(res: &AppResponse, key: &str) -> Vec<String> {
+        res.events
+            .iter()
+            .filter(|e| e.ty == "wasm")
+            .flat_map(|e| &e.attributes)
+            .filter(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+            .collect()
+    }
+
+    fn create_swap(app: &mut TestApp, cosmoswap_controller_addr: &Addr) -> Addr {
+        let msg = ExecuteMsg::CreateSwap {
+            swap_info: SwapInfo {
+                user1: USER1.to_string(),
+                user2: USER2.to_string(),
+                coin1: SwapCoin {
+                    is_native: true,
+                    coin: coin(1_000, DENOM1),
+                    cw20_address: None,
+                },
+                coin2: SwapCoin {
+                    is_native: true,
+                    coin: coin(5_000, DENOM2),
+                    cw20_address: None,
+                },
+                user1_recipient: None,
+                arbiter: None,
+                confirmation_window: None,
+                pricing: None,
+            },
+            expiration: Expiration::Never {},
+            code_id: None,
+            hash_lock: None,
+        };
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[coin(1_000, DENOM1)],
+            )
+            .unwrap();
+        Addr::unchecked(&wasm_attribute(&res, "swap_address")[0])
+    }
+
+    #[test]
+    fn test_swap_notifications() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+        let hook_code_id = app.store_code(hook_subscriber());
+        let failing_hook_code_id = app.store_code(failing_hook_subscriber());
+        for code_id in [hook_code_id, failing_hook_code_id] {
+            let hook_addr = app
+                .instantiate_contract(
+                    code_id,
+                    Addr::unchecked(ADMIN),
+                    &Empty {},
+                    &[],
+                    "hook",
+                    None,
+                )
+                .unwrap();
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::AddHook {
+                    address: hook_addr.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        // Failing subscriber does not block the settlement
+        let swap_addr = create_swap(&mut app, &cosmoswap_controller_addr);
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                swap_addr,
+                &CosmoswapExecuteMsg::Accept { recipient: None },
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap();
+        assert_eq!(wasm_attribute(&res, "hook_event"), vec!["completed"]);
+        assert!(wasm_attribute(&res, "action").contains(&"hook_failed".to_string()));
+        let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(950));
+
+        let swap_addr = create_swap(&mut app, &cosmoswap_controller_addr);
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                swap_addr,
+                &CosmoswapExecuteMsg::Cancel {},
+                &[],
+            )
+            .unwrap();
+        assert_eq!(wasm_attribute(&res, "hook_event"), vec!["cancelled"]);
+
+        // Only registered swaps can notify
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr,
+                &ExecuteMsg::NotifySwap {
+                    event: SwapEvent::Completed,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+    }
+}
+
+mod creation_deposit {
+    use super::*;
+
+    fn set_creation_deposit(app: &mut TestApp, cosmoswap_controller_addr: &Addr) {
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::SetCreationDeposit {
+                creation_deposit: Some(CreationDepositConfig {
+                    amount: coin(100, DENOM1),
+                    min_lifetime: 10,
+                    forfeit_percentage: Decimal::percent(50),
+                    forfeit_window: 60,
+                }),
+            },
+            &[],
+        )
+        .unwrap();
+    }
+
+    fn create_swap_msg(confirmation_window: Option<u64>) -> ExecuteMsg {
+        ExecuteMsg::CreateSwap {
+            swap_info: SwapInfo {
+                user1: USER1.to_string(),
+                user2: USER2.to_string(),
+                coin1: SwapCoin {
+                    is_native: true,
+                    coin: coin(1_000, DENOM1),
+                    cw20_address: None,
+                },
+                coin2: SwapCoin {
+                    is_native: true,
+                    coin: coin(5_000, DENOM2),
+                    cw20_address: None,
+                },
+                user1_recipient: None,
+                arbiter: None,
+                confirmation_window,
+                pricing: None,
+            },
+            expiration: Expiration::Never {},
+            code_id: None,
+            hash_lock: None,
+        }
+    }
+
+    fn create_swap(
+        app: &mut TestApp,
+        cosmoswap_controller_addr: &Addr,
+        confirmation_window: Option<u64>,
+    ) -> Addr {
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &create_swap_msg(confirmation_window),
+                &[coin(1_100, DENOM1)],
+            )
+            .unwrap();
+        let swap_address = res
+            .events
+            .iter()
+            .filter(|e| e.ty == "wasm")
+            .flat_map(|e| &e.attributes)
+            .find(|attr| attr.key == "swap_address")
+            .unwrap();
+        Addr::unchecked(&swap_address.value)
+    }
+
+    #[test]
+    fn test_set_creation_deposit() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+        let config = CreationDepositConfig {
+            amount: coin(0, DENOM1),
+            min_lifetime: 10,
+            forfeit_percentage: Decimal::percent(50),
+            forfeit_window: 60,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::SetCreationDeposit {
+                    creation_deposit: Some(config.clone()),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+        let err = app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::SetCreationDeposit {
+                    creation_deposit: Some(config),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::InvalidCreationDeposit {}.to_string()
+        );
+
+        set_creation_deposit(&mut app, &cosmoswap_controller_addr);
+        let res: Option<CreationDepositConfig> = app
+            .wrap()
+            .query_wasm_smart(
+                cosmoswap_controller_addr.clone(),
+                &QueryMsg::CreationDepositConfig {},
+            )
+            .unwrap();
+        assert_eq!(res.unwrap().amount, coin(100, DENOM1));
+
+        // Swaps can not be created without the deposit
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &create_swap_msg(None),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::MissingCreationDeposit {}.to_string()
+        );
+
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::SetCreationDeposit {
+                creation_deposit: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr,
+            &create_swap_msg(None),
+            &[coin(1_000, DENOM1)],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_cw20_swap() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+        set_creation_deposit(&mut app, &cosmoswap_controller_addr);
+        let cw20_addr = setup_cw20_token(&mut app);
+
+        let msg = ReceiveMsg::CreateSwap {
+            swap_info: SwapInfo {
+                user1: USER1.to_string(),
+                user2: USER2.to_string(),
+                coin1: SwapCoin {
+                    is_native: false,
+                    coin: coin(1_000, CW20_TICKER),
+                    cw20_address: Some(cw20_addr.to_string()),
+                },
+                coin2: SwapCoin {
+                    is_native: true,
+                    coin: coin(5_000, DENOM2),
+                    cw20_address: None,
+                },
+                user1_recipient: None,
+                arbiter: None,
+                confirmation_window: None,
+                pricing: None,
+            },
+            expiration: Expiration::Never {},
+            code_id: None,
+            hash_lock: None,
+        };
+        let send_msg = Cw20ExecuteMsg::Send {
+            contract: cosmoswap_controller_addr.to_string(),
+            amount: Uint128::new(1_000),
+            msg: to_json_binary(&msg).unwrap(),
+        };
+
+        // Cw20 sends can not carry the deposit, it has to be deposited beforehand
+        let err = app
+            .execute_contract(Addr::unchecked(USER1), cw20_addr.clone(), &send_msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().source().unwrap().to_string(),
+            ContractError::MissingCreationDeposit {}.to_string()
+        );
+
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::Deposit {},
+            &[coin(100, DENOM1)],
+        )
+        .unwrap();
+        let res = app
+            .execute_contract(Addr::unchecked(USER1), cw20_addr, &send_msg, &[])
+            .unwrap();
+        let swap_addr = res
+            .events
+            .iter()
+            .filter(|e| e.ty == "wasm")
+            .flat_map(|e| &e.attributes)
+            .find(|attr| attr.key == "swap_address")
+            .map(|attr| Addr::unchecked(&attr.value))
+            .unwrap();
+
+        let res: DepositsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cosmoswap_controller_addr.clone(),
+                &QueryMsg::Deposits {
+                    address: USER1.to_string(),
+                },
+            )
+            .unwrap();
+        assert!(res.deposits.is_empty());
+        let res: Option<CreationDeposit> = app
+            .wrap()
+            .query_wasm_smart(
+                cosmoswap_controller_addr,
+                &QueryMsg::CreationDeposit { swap_id: 1 },
+            )
+            .unwrap();
+        assert_eq!(res.unwrap().terms.amount, coin(100, DENOM1));
+
+        // Deposit is refunded to user1 on completion
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            swap_addr,
+            &CosmoswapExecuteMsg::Accept { recipient: None },
+            &[coin(5_000, DENOM2)],
+        )
+        .unwrap();
+        let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(1_000_000));
+    }
+
+    // Native funds left after the deposit can not be sent along with a cw20 coin1
+    #[test]
+    fn test_cw20_allowance_swap() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+        set_creation_deposit(&mut app, &cosmoswap_controller_addr);
+        let cw20_addr = setup_cw20_token(&mut app);
+
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cw20_addr.clone(),
+            &Cw20ExecuteMsg::IncreaseAllowance {
+                spender: cosmoswap_controller_addr.to_string(),
+                amount: Uint128::new(1_000),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let msg = ExecuteMsg::CreateSwap {
+            swap_info: SwapInfo {
+                user1: USER1.to_string(),
+                user2: USER2.to_string(),
+                coin1: SwapCoin {
+                    is_native: false,
+                    coin: coin(1_000, CW20_TICKER),
+                    cw20_address: Some(cw20_addr.to_string()),
+                },
+                coin2: SwapCoin {
+                    is_native: true,
+                    coin: coin(5_000, DENOM2),
+                    cw20_address: None,
+                },
+                user1_recipient: None,
+                arbiter: None,
+                confirmation_window: None,
+                pricing: None,
+            },
+            expiration: Expiration::Never {},
+            code_id: None,
+            hash_lock: None,
+        };
+
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[coin(150, DENOM1)],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::UnexpectedFunds {}.to_string()
+        );
+
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr,
+            &msg,
+            &[coin(100, DENOM1)],
+        )
+        .unwrap();
+        let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(999_900));
+    }
+
+    #[test]
+    fn test_refund_on_completion() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+        set_creation_deposit(&mut app, &cosmoswap_controller_addr);
+
+        let swap_addr = create_swap(&mut app, &cosmoswap_controller_addr, None);
+        let res: Option<CreationDeposit> = app
+            .wrap()
+            .query_wasm_smart(
+                cosmoswap_controller_addr.clone(),
+                &QueryMsg::CreationDeposit { swap_id: 1 },
+            )
+            .unwrap();
+        let res = res.unwrap();
+        assert_eq!(res.maker, Addr::unchecked(USER1));
+        assert_eq!(res.taker, Addr::unchecked(USER2));
+        assert_eq!(res.locked_at, None);
+        let res = app
+            .wrap()
+            .query_balance(&cosmoswap_controller_addr, DENOM1)
+            .unwrap();
+        assert_eq!(res.amount, Uint128::new(100));
+        let res = app.wrap().query_balance(&swap_addr, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(1_000));
+
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            swap_addr,
+            &CosmoswapExecuteMsg::Accept { recipient: None },
+            &[coin(5_000, DENOM2)],
+        )
+        .unwrap();
+
+        let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(999_000));
+        let res: Option<CreationDeposit> = app
+            .wrap()
+            .query_wasm_smart(
+                cosmoswap_controller_addr,
+                &QueryMsg::CreationDeposit { swap_id: 1 },
+            )
+            .unwrap();
+        assert_eq!(res, None);
+    }
+
+    #[test]
+    fn test_cancel_before_min_lifetime() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+        set_creation_deposit(&mut app, &cosmoswap_controller_addr);
+
+        // Deposit goes to the fee payment address
+        let swap_addr = create_swap(&mut app, &cosmoswap_controller_addr, None);
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            swap_addr,
+            &CosmoswapExecuteMsg::Cancel {},
+            &[],
+        )
+        .unwrap();
+        let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(999_900));
+        let res = app.wrap().query_balance(ADMIN, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(100));
+
+        // Deposit is refunded after the minimum lifetime
+        let swap_addr = create_swap(&mut app, &cosmoswap_controller_addr, None);
+        app.update_block(|block| block.time = block.time.plus_seconds(10));
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            swap_addr,
+            &CosmoswapExecuteMsg::Cancel {},
+            &[],
+        )
+        .unwrap();
+        let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(999_900));
+    }
+
+    #[test]
+    fn test_forfeit() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+        set_creation_deposit(&mut app, &cosmoswap_controller_addr);
+
+        let swap_addr = create_swap(&mut app, &cosmoswap_controller_addr, Some(100));
+        app.update_block(|block| block.time = block.time.plus_seconds(20));
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            swap_addr.clone(),
+            &CosmoswapExecuteMsg::Accept { recipient: None },
+            &[coin(5_000, DENOM2)],
+        )
+        .unwrap();
+        let res: Option<CreationDeposit> = app
+            .wrap()
+            .query_wasm_smart(
+                cosmoswap_controller_addr.clone(),
+                &QueryMsg::CreationDeposit { swap_id: 1 },
+            )
+            .unwrap();
+        assert_eq!(res.unwrap().locked_at, Some(app.block_info().time));
+
+        // Cancelling right after user2 deposited pays half of the deposit to user2
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            swap_addr,
+            &CosmoswapExecuteMsg::Cancel {},
+            &[],
+        )
+        .unwrap();
+        let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(50));
+        let res = app.wrap().query_balance(USER2, DENOM2).unwrap();
+        assert_eq!(res.amount, Uint128::new(1_000_000));
+        let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(999_950));
+    }
+}
+
+mod sweep {
+    use super::*;
+
+    fn create_swap(
+        app: &mut TestApp,
+        cosmoswap_controller_addr: &Addr,
+        expiration: Expiration,
+        hash_lock: Option<HexBinary>,
+        funds: &[Coin],
+    ) -> Addr {
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::CreateSwap {
+                    swap_info: SwapInfo {
+                        user1: USER1.to_string(),
+                        user2: USER2.to_string(),
+                        coin1: SwapCoin {
+                            is_native: true,
+                            coin: coin(1_000, DENOM1),
+                            cw20_address: None,
+                        },
+                        coin2: SwapCoin {
+                            is_native: true,
+                            coin: coin(5_000, DENOM2),
+                            cw20_address: None,
+                        },
+                        user1_recipient: None,
+                        arbiter: None,
+                        confirmation_window: None,
+                        pricing: None,
+                    },
+                    expiration,
+                    code_id: None,
+                    hash_lock,
+                },
+                funds,
+            )
+            .unwrap();
+        let swap_address = res
+            .events
+            .iter()
+            .filter(|e| e.ty == "wasm")
+            .flat_map(|e| &e.attributes)
+            .find(|attr| attr.key == "swap_address")
+            .unwrap();
+        Addr::unchecked(&swap_address.value)
+    }
+
+    fn set_sweep_bounty(app: &mut TestApp, cosmoswap_controller_addr: &Addr) {
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::SetSweepBounty {
+                bounty: Some(coin(10, DENOM1)),
+            },
+            &[],
+        )
+        .unwrap();
+    }
+
+    fn swap_status(
+        app: &TestApp,
+        cosmoswap_controller_addr: &Addr,
+        swap_id: u64,
+    ) -> Option<RegistryStatus> {
+        app.wrap()
+            .query_wasm_smart(cosmoswap_controller_addr, &QueryMsg::SwapStatus { swap_id })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_set_sweep_bounty() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::SetSweepBounty {
+                    bounty: Some(coin(10, DENOM1)),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+        let err = app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::SetSweepBounty {
+                    bounty: Some(coin(0, DENOM1)),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::InvalidSweepBounty {}.to_string()
+        );
+
+        set_sweep_bounty(&mut app, &cosmoswap_controller_addr);
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::FundSweepPool {},
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            FundsError::MissingFunds {}.to_string()
+        );
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::FundSweepPool {},
+            &[coin(100, DENOM1)],
+        )
+        .unwrap();
+
+        let res: SweepBountyResponse = app
+            .wrap()
+            .query_wasm_smart(cosmoswap_controller_addr, &QueryMsg::SweepBounty {})
+            .unwrap();
+        assert_eq!(
+            res,
+            SweepBountyResponse {
+                bounty: Some(coin(10, DENOM1)),
+                pool: vec![coin(100, DENOM1)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_sweep_from_pool() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+        set_sweep_bounty(&mut app, &cosmoswap_controller_addr);
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::FundSweepPool {},
+            &[coin(100, DENOM1)],
+        )
+        .unwrap();
+
+        let height = app.block_info().height;
+        create_swap(
+            &mut app,
+            &cosmoswap_controller_addr,
+            Expiration::AtHeight(height + 5),
+            None,
+            &[coin(1_000, DENOM1)],
+        );
+        let swap_addr = create_swap(
+            &mut app,
+            &cosmoswap_controller_addr,
+            Expiration::Never {},
+            None,
+            &[coin(1_000, DENOM1)],
+        );
+        create_swap(
+            &mut app,
+            &cosmoswap_controller_addr,
+            Expiration::AtHeight(height + 20),
+            None,
+            &[coin(1_000, DENOM1)],
+        );
+        assert_eq!(
+            swap_status(&app, &cosmoswap_controller_addr, 1),
+            Some(RegistryStatus::Open {})
+        );
+
+        let err = app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::SweepExpired { limit: None },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::NothingToSweep {}.to_string()
+        );
+
+        // Only the first swap is expired
+        app.update_block(|block| block.height += 10);
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::SweepExpired { limit: None },
+            &[],
+        )
+        .unwrap();
+
+        let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(997_900));
+        let res = app.wrap().query_balance(ADMIN, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(10));
+        assert_eq!(
+            swap_status(&app, &cosmoswap_controller_addr, 1),
+            Some(RegistryStatus::Swept {
+                keeper: Addr::unchecked(ADMIN)
+            })
+        );
+        assert_eq!(
+            swap_status(&app, &cosmoswap_controller_addr, 3),
+            Some(RegistryStatus::Open {})
+        );
+        let res: SweepBountyResponse = app
+            .wrap()
+            .query_wasm_smart(cosmoswap_controller_addr.clone(), &QueryMsg::SweepBounty {})
+            .unwrap();
+        assert_eq!(res.pool, vec![coin(90, DENOM1)]);
+
+        let err = app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::SweepExpired { limit: None },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::NothingToSweep {}.to_string()
+        );
+
+        // Closed swaps are recorded with their closing event
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            swap_addr,
+            &CosmoswapExecuteMsg::Cancel {},
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            swap_status(&app, &cosmoswap_controller_addr, 2),
+            Some(RegistryStatus::Closed {
+                event: SwapEvent::Cancelled
+            })
+        );
+    }
+
+    // Swaps are swept in expiration order, unexpired ones are left in the index
+    #[test]
+    fn test_sweep_by_expiration() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+        let block = app.block_info();
+        for expiration in [
+            Expiration::AtHeight(block.height + 20),
+            Expiration::AtTime(block.time.plus_seconds(5)),
+            Expiration::AtHeight(block.height + 5),
+            Expiration::AtHeight(block.height + 10),
+        ] {
+            create_swap(
+                &mut app,
+                &cosmoswap_controller_addr,
+                expiration,
+                None,
+                &[coin(1_000, DENOM1)],
+            );
+        }
+        app.update_block(|block| {
+            block.height += 10;
+            block.time = block.time.plus_seconds(10);
+        });
+
+        let keeper = app.api().addr_make("keeper");
+        let res = app
+            .execute_contract(
+                keeper.clone(),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::SweepExpired { limit: Some(2) },
+                &[],
+            )
+            .unwrap();
+        assert!(res
+            .events
+            .iter()
+            .flat_map(|e| &e.attributes)
+            .any(|attr| attr.key == "swap_ids" && attr.value == "3,4"));
+        let res = app
+            .execute_contract(
+                keeper.clone(),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::SweepExpired { limit: Some(2) },
+                &[],
+            )
+            .unwrap();
+        assert!(res
+            .events
+            .iter()
+            .flat_map(|e| &e.attributes)
+            .any(|attr| attr.key == "swap_ids" && attr.value == "2"));
+        assert_eq!(
+            swap_status(&app, &cosmoswap_controller_addr, 1),
+            Some(RegistryStatus::Open {})
+        );
+
+        let err = app
+            .execute_contract(
+                keeper,
+                cosmoswap_controller_addr,
+                &ExecuteMsg::SweepExpired { limit: None },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::NothingToSweep {}.to_string()
+        );
+    }
+
+    // Makers sweeping their own swaps are not paid a bounty
+    #[test]
+    fn test_self_sweep() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+        set_sweep_bounty(&mut app, &cosmoswap_controller_addr);
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::FundSweepPool {},
+            &[coin(100, DENOM1)],
+        )
+        .unwrap();
+
+        let height = app.block_info().height;
+        create_swap(
+            &mut app,
+            &cosmoswap_controller_addr,
+            Expiration::AtHeight(height + 1),
+            None,
+            &[coin(1_000, DENOM1)],
+        );
+        app.update_block(|block| block.height += 1);
+
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::SweepExpired { limit: None },
+            &[],
+        )
+        .unwrap();
+        let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(999_900));
+        assert_eq!(
+            swap_status(&app, &cosmoswap_controller_addr, 1),
+            Some(RegistryStatus::Swept {
+                keeper: Addr::unchecked(USER1)
+            })
+        );
+        let res: SweepBountyResponse = app
+            .wrap()
+            .query_wasm_smart(cosmoswap_controller_addr, &QueryMsg::SweepBounty {})
+            .unwrap();
+        assert_eq!(res.pool, vec![coin(100, DENOM1)]);
+    }
+
+    #[test]
+    fn test_drop_disputed() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+        set_sweep_bounty(&mut app, &cosmoswap_controller_addr);
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::FundSweepPool {},
+            &[coin(100, DENOM1)],
+        )
+        .unwrap();
+
+        let height = app.block_info().height;
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::CreateSwap {
+                    swap_info: SwapInfo {
+                        user1: USER1.to_string(),
+                        user2: USER2.to_string(),
+                        coin1: SwapCoin {
+                            is_native: true,
+                            coin: coin(1_000, DENOM1),
+                            cw20_address: None,
+                        },
+                        coin2: SwapCoin {
+                            is_native: true,
+                            coin: coin(5_000, DENOM2),
+                            cw20_address: None,
+                        },
+                        user1_recipient: None,
+                        arbiter: Some(Arbiter {
+                            address: ADMIN.to_string(),
+                            fee_percentage: Decimal::percent(1),
+                        }),
+                        confirmation_window: None,
+                        pricing: None,
+                    },
+                    expiration: Expiration::AtHeight(height + 5),
+                    code_id: None,
+                    hash_lock: None,
+                },
+                &[coin(1_000, DENOM1)],
+            )
+            .unwrap();
+        let disputed_addr = res
+            .events
+            .iter()
+            .filter(|e| e.ty == "wasm")
+            .flat_map(|e| &e.attributes)
+            .find(|attr| attr.key == "swap_address")
+            .map(|attr| Addr::unchecked(&attr.value))
+            .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            disputed_addr,
+            &CosmoswapExecuteMsg::RaiseDispute {},
+            &[],
+        )
+        .unwrap();
+        create_swap(
+            &mut app,
+            &cosmoswap_controller_addr,
+            Expiration::AtHeight(height + 5),
+            None,
+            &[coin(1_000, DENOM1)],
+        );
+        app.update_block(|block| block.height += 10);
+
+        // Disputed swap is dropped from the index without a bounty, the arbiter closes it
+        let keeper = app.api().addr_make("keeper");
+        let msg = ExecuteMsg::SweepExpired { limit: Some(1) };
+        let res = app
+            .execute_contract(keeper.clone(), cosmoswap_controller_addr.clone(), &msg, &[])
+            .unwrap();
+        assert!(res
+            .events
+            .iter()
+            .flat_map(|e| &e.attributes)
+            .any(|attr| attr.key == "dropped_swap_ids" && attr.value == "1"));
+        let res = app.wrap().query_balance(&keeper, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::zero());
+        assert_eq!(
+            swap_status(&app, &cosmoswap_controller_addr, 1),
+            Some(RegistryStatus::Open {})
+        );
+
+        app.execute_contract(keeper.clone(), cosmoswap_controller_addr.clone(), &msg, &[])
+            .unwrap();
+        let res = app.wrap().query_balance(&keeper, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(10));
+        assert_eq!(
+            swap_status(&app, &cosmoswap_controller_addr, 2),
+            Some(RegistryStatus::Swept {
+                keeper: keeper.clone()
+            })
+        );
+
+        let err = app
+            .execute_contract(keeper, cosmoswap_controller_addr, &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::NothingToSweep {}.to_string()
+        );
+    }
+
+    #[test]
+    fn test_sweep_from_creation_deposit() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+        set_sweep_bounty(&mut app, &cosmoswap_controller_addr);
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::SetCreationDeposit {
+                creation_deposit: Some(CreationDepositConfig {
+                    amount: coin(100, DENOM1),
+                    min_lifetime: 10,
+                    forfeit_percentage: Decimal::percent(50),
+                    forfeit_window: 60,
+                }),
+            },
+            &[],
+        )
+        .unwrap();
+
+        let time = app.block_info().time;
+        create_swap(
+            &mut app,
+            &cosmoswap_controller_addr,
+            Expiration::AtTime(time.plus_seconds(5)),
+            None,
+            &[coin(1_100, DENOM1)],
+        );
+        create_swap(
+            &mut app,
+            &cosmoswap_controller_addr,
+            Expiration::AtTime(time.plus_seconds(5)),
+            Some(HexBinary::from(Sha256::digest(b"secret").to_vec())),
+            &[coin(1_100, DENOM1)],
+        );
+        app.update_block(|block| block.time = block.time.plus_seconds(5));
+
+        let keeper = app.api().addr_make("keeper");
+        app.execute_contract(
+            keeper.clone(),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::SweepExpired { limit: Some(1) },
+            &[],
+        )
+        .unwrap();
+        // Swept before the minimum lifetime, the rest of the deposit goes to the fee address
+        let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(998_800));
+        let res = app.wrap().query_balance(&keeper, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(10));
+        let res = app.wrap().query_balance(ADMIN, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(90));
+        let res: Option<CreationDeposit> = app
+            .wrap()
+            .query_wasm_smart(
+                cosmoswap_controller_addr.clone(),
+                &QueryMsg::CreationDeposit { swap_id: 1 },
+            )
+            .unwrap();
+        assert_eq!(res, None);
+
+        // Hashed time-locked swaps are refunded
+        app.update_block(|block| block.time = block.time.plus_seconds(5));
+        let res = app
+            .execute_contract(
+                keeper.clone(),
+                cosmoswap_controller_addr.clone(),
+                &ExecuteMsg::SweepExpired { limit: Some(1) },
+                &[],
+            )
+            .unwrap();
+        assert!(res
+            .events
+            .iter()
+            .flat_map(|e| &e.attributes)
+            .any(|attr| attr.key == "action" && attr.value == "refund"));
+        let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(999_890));
+        let res = app.wrap().query_balance(&keeper, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(20));
+        assert_eq!(
+            swap_status(&app, &cosmoswap_controller_addr, 2),
+            Some(RegistryStatus::Swept { keeper })
+        );
+    }
+}
+
+mod offers {
+    use super::*;
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32].into()).unwrap()
+    }
+
+    fn pubkey(key: &SigningKey) -> Binary {
+        Binary::from(key.verifying_key().to_encoded_point(true).as_bytes())
+    }
+
+    // Address derived from the public key like the chain does
+    fn address(app: &TestApp, key: &SigningKey) -> Addr {
+        let hash = Ripemd160::digest(Sha256::digest(pubkey(key).as_slice()));
+        app.api()
+            .addr_humanize(&CanonicalAddr::from(hash.as_slice()))
+            .unwrap()
+    }
+
+    fn sign(key: &SigningKey, maker: &Addr, offer: &Offer) -> Binary {
+        let sign_doc = adr036_sign_doc(maker, &to_json_binary(offer).unwrap());
+        let signature: Signature = key
+            .sign_prehash(&Sha256::digest(sign_doc.as_bytes()))
+            .unwrap();
+        Binary::from(signature.to_bytes().as_slice())
+    }
+
+    fn native_offer(controller: &Addr, maker: &Addr, nonce: u64) -> Offer {
+        Offer {
+            controller: controller.to_string(),
+            swap_info: SwapInfo {
+                user1: maker.to_string(),
+                user2: USER2.to_string(),
+                coin1: SwapCoin {
+                    is_native: true,
+                    coin: coin(1_000, DENOM1),
+                    cw20_address: None,
+                },
+                coin2: SwapCoin {
+                    is_native: true,
+                    coin: coin(5_000, DENOM2),
+                    cw20_address: None,
+                },
+                user1_recipient: None,
+                arbiter: None,
+                confirmation_window: None,
+                pricing: None,
+            },
+            expiration: Expiration::Never {},
+            nonce,
+        }
+    }
+
+    #[test]
+    fn test_native_offer() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+        let key = signing_key(1);
+        let maker = address(&app, &key);
+        app.send_tokens(
+            Addr::unchecked(USER1),
+            maker.clone(),
+            &[coin(1_000, DENOM1)],
+        )
+        .unwrap();
+        app.execute_contract(
+            maker.clone(),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::Deposit {},
+            &[coin(1_000, DENOM1)],
+        )
+        .unwrap();
+        let res: DepositsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cosmoswap_controller_addr.clone(),
+                &QueryMsg::Deposits {
+                    address: maker.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.deposits, vec![coin(1_000, DENOM1)]);
+
+        let hook_code_id = app.store_code(hook_subscriber());
+        let hook_addr = app
+            .instantiate_contract(
+                hook_code_id,
+                Addr::unchecked(ADMIN),
+                &Empty {},
+                &[],
+                "hook",
+                None,
+            )
+            .unwrap();
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::AddHook {
+                address: hook_addr.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        let offer = native_offer(&cosmoswap_controller_addr, &maker, 0);
+        let msg = ExecuteMsg::TakeOffer {
+            signature: sign(&key, &maker, &offer),
+            pubkey: pubkey(&key),
+            offer,
+        };
+        let res = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap();
+
+        // Subscribers are notified of taken offers
+        assert!(res
+            .events
+            .iter()
+            .flat_map(|e| &e.attributes)
+            .any(|attr| attr.key == "hook_event" && attr.value == "offer_taken"));
+
+        let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(950));
+        let res = app.wrap().query_balance(&maker, DENOM2).unwrap();
+        assert_eq!(res.amount, Uint128::new(4_750));
+        let res = app.wrap().query_balance(ADMIN, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(50));
+        let res = app.wrap().query_balance(ADMIN, DENOM2).unwrap();
+        assert_eq!(res.amount, Uint128::new(250));
+        let res: DepositsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cosmoswap_controller_addr.clone(),
+                &QueryMsg::Deposits {
+                    address: maker.to_string(),
+                },
+            )
+            .unwrap();
+        assert!(res.deposits.is_empty());
+
+        // Offer cannot be replayed
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_controller_addr,
+                &msg,
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::NonceUsed { nonce: 0 }.to_string()
+        );
+    }
+
+    #[test]
+    fn test_cw20_offer() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+        let cw20_addr = setup_cw20_token(&mut app);
+
+        let key = signing_key(2);
+        let maker = address(&app, &key);
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cw20_addr.clone(),
+            &Cw20ExecuteMsg::Transfer {
+                recipient: maker.to_string(),
+                amount: Uint128::new(1_000),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            maker.clone(),
+            cw20_addr.clone(),
+            &Cw20ExecuteMsg::IncreaseAllowance {
+                spender: cosmoswap_controller_addr.to_string(),
+                amount: Uint128::new(1_000),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        let mut offer = native_offer(&cosmoswap_controller_addr, &maker, 0);
+        offer.swap_info.coin1 = SwapCoin {
+            is_native: false,
+            coin: coin(1_000, CW20_TICKER),
+            cw20_address: Some(cw20_addr.to_string()),
+        };
+        let msg = ExecuteMsg::TakeOffer {
+            signature: sign(&key, &maker, &offer),
+            pubkey: pubkey(&key),
+            offer,
+        };
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            cosmoswap_controller_addr,
+            &msg,
+            &[coin(5_000, DENOM2)],
+        )
+        .unwrap();
+
+        let res: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_addr,
+                &Cw20QueryMsg::Balance {
+                    address: USER2.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.balance, Uint128::new(950));
+        let res = app.wrap().query_balance(&maker, DENOM2).unwrap();
+        assert_eq!(res.amount, Uint128::new(4_750));
+    }
+
+    #[test]
+    fn test_invalid_offer() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+        let key = signing_key(3);
+        let maker = address(&app, &key);
+        let offer = native_offer(&cosmoswap_controller_addr, &maker, 0);
+
+        // Nothing is deposited
+        let msg = ExecuteMsg::TakeOffer {
+            signature: sign(&key, &maker, &offer),
+            pubkey: pubkey(&key),
+            offer: offer.clone(),
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::InsufficientDeposit {}.to_string()
+        );
+
+        // Signed by someone else
+        let other_key = signing_key(4);
+        let msg = ExecuteMsg::TakeOffer {
+            signature: sign(&other_key, &maker, &offer),
+            pubkey: pubkey(&other_key),
+            offer: offer.clone(),
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::InvalidSignature {}.to_string()
+        );
+
+        // Offer is changed after signing
+        let mut changed_offer = offer.clone();
+        changed_offer.swap_info.coin1.coin.amount = Uint128::new(1);
+        let msg = ExecuteMsg::TakeOffer {
+            signature: sign(&key, &maker, &offer),
+            pubkey: pubkey(&key),
+            offer: changed_offer,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::InvalidSignature {}.to_string()
+        );
+
+        // Cancelled nonce
+        app.execute_contract(
+            maker.clone(),
+            cosmoswap_controller_addr.clone(),
+            &ExecuteMsg::CancelNonce { nonce: 0 },
+            &[],
+        )
+        .unwrap();
+        let res: IsNonceUsedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cosmoswap_controller_addr.clone(),
+                &QueryMsg::IsNonceUsed {
+                    maker: maker.to_string(),
+                    nonce: 0,
+                },
+            )
+            .unwrap();
+        assert!(res.used);
+        let msg = ExecuteMsg::TakeOffer {
+            signature: sign(&key, &maker, &offer),
+            pubkey: pubkey(&key),
+            offer,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_controller_addr.clone(),
+                &msg,
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::NonceUsed { nonce: 0 }.to_string()
+        );
+
+        // Expired offer
+        let mut offer = native_offer(&cosmoswap_controller_addr, &maker, 1);
+        offer.expiration = Expiration::AtHeight(app.block_info().height);
+        let msg = ExecuteMsg::TakeOffer {
+            signature: sign(&key, &maker, &offer),
+            pubkey: pubkey(&key),
+            offer,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_controller_addr,
+                &msg,
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::OfferExpired {}.to_string()
+        );
     }
 }
@@ -0,0 +1,253 @@
+use cosmoswap_packages::funds::check_single_coin;
+use cosmoswap_packages::types::SwapInfo;
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_json_binary, BankMsg, Binary, Deps, DepsMut, Empty, Env, IbcMsg, IbcTimeout, MessageInfo,
+    Response, StdResult,
+};
+use cw2::set_contract_version;
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, SwapPacket};
+use crate::state::{
+    MakerSwap, SwapStatus, CHANNELS, ESCROWED_SWAPS, MAKER_SWAPS, SWAP_COUNT, TAKER_SWAPS,
+};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:cosmoswap-ibc";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    SWAP_COUNT.save(deps.storage, &0)?;
+
+    Ok(Response::new().add_attribute("action", "instantiate"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::MakeSwap {
+            channel_id,
+            swap_info,
+            maker_receiver,
+            timeout,
+        } => execute_make_swap(
+            deps,
+            env,
+            info,
+            channel_id,
+            swap_info,
+            maker_receiver,
+            timeout,
+        ),
+        ExecuteMsg::TakeSwap {
+            channel_id,
+            swap_id,
+            taker_receiver,
+        } => execute_take_swap(deps, env, info, channel_id, swap_id, taker_receiver),
+        ExecuteMsg::CancelSwap { swap_id } => execute_cancel_swap(deps, env, info, swap_id),
+        ExecuteMsg::Reclaim { swap_id } => execute_reclaim(deps, env, info, swap_id),
+    }
+}
+
+fn execute_make_swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel_id: String,
+    swap_info: SwapInfo,
+    maker_receiver: String,
+    timeout: u64,
+) -> Result<Response, ContractError> {
+    if !CHANNELS.has(deps.storage, &channel_id) {
+        return Err(ContractError::UnknownChannel { channel_id });
+    }
+    if !swap_info.coin1.is_native || !swap_info.coin2.is_native {
+        return Err(ContractError::InvalidSwapCoin {});
+    }
+    // Receivers are set with maker_receiver and taker_receiver instead of the swap recipients
+    if swap_info.user1_recipient.is_some()
+        || swap_info.arbiter.is_some()
+        || swap_info.confirmation_window.is_some()
+        || swap_info.pricing.is_some()
+    {
+        return Err(ContractError::UnsupportedSwapInfo {});
+    }
+    if timeout == 0 {
+        return Err(ContractError::InvalidTimeout {});
+    }
+
+    let maker = deps.api.addr_validate(&swap_info.user1)?;
+    if info.sender != maker {
+        return Err(ContractError::Unauthorized {});
+    }
+    check_single_coin(&info, &swap_info.coin1.coin)?;
+
+    let swap_id = SWAP_COUNT.load(deps.storage)? + 1;
+    SWAP_COUNT.save(deps.storage, &swap_id)?;
+
+    let expiration = env.block.time.plus_seconds(timeout);
+    MAKER_SWAPS.save(
+        deps.storage,
+        swap_id,
+        &MakerSwap {
+            channel_id: channel_id.clone(),
+            maker,
+            maker_receiver: maker_receiver.clone(),
+            swap_info: swap_info.clone(),
+            expiration,
+            status: SwapStatus::Open,
+        },
+    )?;
+    ESCROWED_SWAPS.save(deps.storage, (&channel_id, swap_id), &Empty {})?;
+
+    let packet = SwapPacket::MakeSwap {
+        swap_id,
+        swap_info,
+        maker_receiver,
+        expiration,
+    };
+    let msg = IbcMsg::SendPacket {
+        channel_id: channel_id.clone(),
+        data: to_json_binary(&packet)?,
+        timeout: IbcTimeout::with_timestamp(expiration),
+    };
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "make_swap")
+        .add_attribute("swap_id", swap_id.to_string())
+        .add_attribute("channel_id", channel_id))
+}
+
+fn execute_take_swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel_id: String,
+    swap_id: u64,
+    taker_receiver: String,
+) -> Result<Response, ContractError> {
+    let mut swap = TAKER_SWAPS.load(deps.storage, (&channel_id, swap_id))?;
+    if swap.status != SwapStatus::Open {
+        return Err(ContractError::SwapLocked {});
+    }
+    if env.block.time >= swap.expiration {
+        return Err(ContractError::SwapExpired {});
+    }
+    if info.sender != swap.swap_info.user2 {
+        return Err(ContractError::Unauthorized {});
+    }
+    check_single_coin(&info, &swap.swap_info.coin2.coin)?;
+
+    swap.status = SwapStatus::Taking;
+    swap.taker = Some(info.sender);
+    swap.taker_receiver = Some(taker_receiver.clone());
+    TAKER_SWAPS.save(deps.storage, (&channel_id, swap_id), &swap)?;
+
+    let packet = SwapPacket::TakeSwap {
+        swap_id,
+        taker_receiver,
+    };
+    let msg = IbcMsg::SendPacket {
+        channel_id: channel_id.clone(),
+        data: to_json_binary(&packet)?,
+        timeout: IbcTimeout::with_timestamp(swap.expiration),
+    };
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "take_swap")
+        .add_attribute("swap_id", swap_id.to_string())
+        .add_attribute("channel_id", channel_id))
+}
+
+// Refund timeout for cancel packets
+const CANCEL_TIMEOUT: u64 = 60 * 60;
+
+fn execute_cancel_swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    swap_id: u64,
+) -> Result<Response, ContractError> {
+    let mut swap = MAKER_SWAPS.load(deps.storage, swap_id)?;
+    if info.sender != swap.maker {
+        return Err(ContractError::Unauthorized {});
+    }
+    if swap.status != SwapStatus::Open {
+        return Err(ContractError::SwapLocked {});
+    }
+
+    swap.status = SwapStatus::Cancelling;
+    MAKER_SWAPS.save(deps.storage, swap_id, &swap)?;
+
+    let packet = SwapPacket::CancelSwap { swap_id };
+    let msg = IbcMsg::SendPacket {
+        channel_id: swap.channel_id,
+        data: to_json_binary(&packet)?,
+        timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(CANCEL_TIMEOUT)),
+    };
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "cancel_swap")
+        .add_attribute("swap_id", swap_id.to_string()))
+}
+
+/// Take packets time out at the expiration, so an open swap can no longer be taken after it
+fn execute_reclaim(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    swap_id: u64,
+) -> Result<Response, ContractError> {
+    let mut swap = MAKER_SWAPS.load(deps.storage, swap_id)?;
+    if swap.status != SwapStatus::Open {
+        return Err(ContractError::SwapLocked {});
+    }
+    if env.block.time < swap.expiration {
+        return Err(ContractError::SwapNotExpired {});
+    }
+
+    swap.status = SwapStatus::Refunded;
+    MAKER_SWAPS.save(deps.storage, swap_id, &swap)?;
+    ESCROWED_SWAPS.remove(deps.storage, (&swap.channel_id, swap_id));
+
+    let msg = BankMsg::Send {
+        to_address: swap.maker.to_string(),
+        amount: vec![swap.swap_info.coin1.coin],
+    };
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "reclaim")
+        .add_attribute("swap_id", swap_id.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::MakerSwap { swap_id } => {
+            to_json_binary(&MAKER_SWAPS.load(deps.storage, swap_id)?)
+        }
+        QueryMsg::TakerSwap {
+            channel_id,
+            swap_id,
+        } => to_json_binary(&TAKER_SWAPS.load(deps.storage, (&channel_id, swap_id))?),
+    }
+}
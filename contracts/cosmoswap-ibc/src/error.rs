@@ -0,0 +1,44 @@
+use cosmoswap_packages::funds::FundsError;
+use cosmwasm_std::{IbcOrder, StdError};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Only native coins can be swapped over IBC")]
+    InvalidSwapCoin {},
+
+    #[error(
+        "Recipients, arbiters, confirmation windows and dutch auctions are not supported over IBC"
+    )]
+    UnsupportedSwapInfo {},
+
+    #[error("Invalid timeout")]
+    InvalidTimeout {},
+
+    #[error("Swap is expired")]
+    SwapExpired {},
+
+    #[error("Swap is not active")]
+    SwapLocked {},
+
+    #[error("Swap is not expired")]
+    SwapNotExpired {},
+
+    #[error("Unknown channel {channel_id}")]
+    UnknownChannel { channel_id: String },
+
+    #[error("Only {order:?} channels are supported")]
+    InvalidChannelOrder { order: IbcOrder },
+
+    #[error("Invalid channel version! Got: {got} - Expected: {expected}")]
+    InvalidChannelVersion { got: String, expected: String },
+
+    #[error("{0}")]
+    Funds(#[from] FundsError),
+}
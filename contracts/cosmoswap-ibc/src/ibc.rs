@@ -0,0 +1,335 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_json, to_json_binary, BankMsg, Binary, DepsMut, Empty, Env, IbcBasicResponse, IbcChannel,
+    IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcOrder,
+    IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse,
+    Never, Order, StdResult,
+};
+
+use crate::error::ContractError;
+use crate::msg::{SwapAck, SwapPacket};
+use crate::state::{SwapStatus, TakerSwap, CHANNELS, ESCROWED_SWAPS, MAKER_SWAPS, TAKER_SWAPS};
+
+pub const IBC_VERSION: &str = "cosmoswap-1";
+pub const IBC_ORDER: IbcOrder = IbcOrder::Unordered;
+
+fn ack_success() -> Binary {
+    to_json_binary(&SwapAck::Result(Binary::from(b"\x01"))).unwrap()
+}
+
+fn ack_fail(err: String) -> Binary {
+    to_json_binary(&SwapAck::Error(err)).unwrap()
+}
+
+fn validate_channel(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    if channel.order != IBC_ORDER {
+        return Err(ContractError::InvalidChannelOrder { order: IBC_ORDER });
+    }
+    if channel.version != IBC_VERSION {
+        return Err(ContractError::InvalidChannelVersion {
+            got: channel.version.clone(),
+            expected: IBC_VERSION.to_string(),
+        });
+    }
+    if let Some(version) = counterparty_version {
+        if version != IBC_VERSION {
+            return Err(ContractError::InvalidChannelVersion {
+                got: version.to_string(),
+                expected: IBC_VERSION.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<IbcChannelOpenResponse, ContractError> {
+    validate_channel(msg.channel(), msg.counterparty_version())?;
+    Ok(None)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(channel, msg.counterparty_version())?;
+    CHANNELS.save(deps.storage, &channel.endpoint.channel_id, &Empty {})?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_connect")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    let channel_id = channel.endpoint.channel_id.as_str();
+    CHANNELS.remove(deps.storage, channel_id);
+
+    // No take packet can arrive anymore, refund the open and cancelling swaps on the channel.
+    // Packets still in flight time out on close and are ignored since the swap is refunded.
+    let swap_ids = ESCROWED_SWAPS
+        .prefix(channel_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<u64>>>()?;
+    let mut msgs: Vec<BankMsg> = vec![];
+    for swap_id in swap_ids {
+        let mut swap = MAKER_SWAPS.load(deps.storage, swap_id)?;
+        swap.status = SwapStatus::Refunded;
+        MAKER_SWAPS.save(deps.storage, swap_id, &swap)?;
+        ESCROWED_SWAPS.remove(deps.storage, (channel_id, swap_id));
+        msgs.push(BankMsg::Send {
+            to_address: swap.maker.to_string(),
+            amount: vec![swap.swap_info.coin1.coin],
+        });
+    }
+
+    Ok(IbcBasicResponse::new()
+        .add_messages(msgs)
+        .add_attribute("action", "ibc_close")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, Never> {
+    // Errors are returned as error acks so the sender can refund
+    do_ibc_packet_receive(deps, &msg.packet).or_else(|err| {
+        Ok(IbcReceiveResponse::new()
+            .set_ack(ack_fail(err.to_string()))
+            .add_attribute("action", "receive")
+            .add_attribute("error", err.to_string()))
+    })
+}
+
+fn do_ibc_packet_receive(
+    deps: DepsMut,
+    packet: &IbcPacket,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let channel_id = packet.dest.channel_id.as_str();
+    let msg: SwapPacket = from_json(&packet.data)?;
+    match msg {
+        SwapPacket::MakeSwap {
+            swap_id,
+            swap_info,
+            maker_receiver,
+            expiration,
+        } => {
+            if !swap_info.coin1.is_native || !swap_info.coin2.is_native {
+                return Err(ContractError::InvalidSwapCoin {});
+            }
+            deps.api.addr_validate(&swap_info.user2)?;
+            deps.api.addr_validate(&maker_receiver)?;
+
+            let swap = TakerSwap {
+                maker_receiver,
+                swap_info,
+                expiration,
+                taker: None,
+                taker_receiver: None,
+                status: SwapStatus::Open,
+            };
+            TAKER_SWAPS.save(deps.storage, (channel_id, swap_id), &swap)?;
+
+            Ok(IbcReceiveResponse::new()
+                .set_ack(ack_success())
+                .add_attribute("action", "receive_make_swap")
+                .add_attribute("swap_id", swap_id.to_string()))
+        }
+        SwapPacket::TakeSwap {
+            swap_id,
+            taker_receiver,
+        } => {
+            let mut swap = MAKER_SWAPS.load(deps.storage, swap_id)?;
+            if swap.channel_id != channel_id || swap.status != SwapStatus::Open {
+                return Err(ContractError::SwapLocked {});
+            }
+            let taker_receiver = deps.api.addr_validate(&taker_receiver)?;
+
+            swap.status = SwapStatus::Completed;
+            MAKER_SWAPS.save(deps.storage, swap_id, &swap)?;
+            ESCROWED_SWAPS.remove(deps.storage, (channel_id, swap_id));
+
+            let msg = BankMsg::Send {
+                to_address: taker_receiver.to_string(),
+                amount: vec![swap.swap_info.coin1.coin],
+            };
+
+            Ok(IbcReceiveResponse::new()
+                .set_ack(ack_success())
+                .add_message(msg)
+                .add_attribute("action", "receive_take_swap")
+                .add_attribute("swap_id", swap_id.to_string()))
+        }
+        SwapPacket::CancelSwap { swap_id } => {
+            let mut swap = TAKER_SWAPS.load(deps.storage, (channel_id, swap_id))?;
+            if swap.status != SwapStatus::Open {
+                return Err(ContractError::SwapLocked {});
+            }
+
+            swap.status = SwapStatus::Cancelled;
+            TAKER_SWAPS.save(deps.storage, (channel_id, swap_id), &swap)?;
+
+            Ok(IbcReceiveResponse::new()
+                .set_ack(ack_success())
+                .add_attribute("action", "receive_cancel_swap")
+                .add_attribute("swap_id", swap_id.to_string()))
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let ack: SwapAck = from_json(&msg.acknowledgement.data)?;
+    match ack {
+        SwapAck::Result(_) => on_packet_success(deps, &msg.original_packet),
+        SwapAck::Error(err) => on_packet_failure(deps, &msg.original_packet, err),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    on_packet_failure(deps, &msg.packet, "timeout".to_string())
+}
+
+fn on_packet_success(deps: DepsMut, packet: &IbcPacket) -> Result<IbcBasicResponse, ContractError> {
+    let channel_id = packet.src.channel_id.as_str();
+    let msg: SwapPacket = from_json(&packet.data)?;
+    match msg {
+        SwapPacket::MakeSwap { swap_id, .. } => Ok(IbcBasicResponse::new()
+            .add_attribute("action", "ack_make_swap")
+            .add_attribute("swap_id", swap_id.to_string())),
+        SwapPacket::TakeSwap { swap_id, .. } => {
+            // Maker chain released coin1, release coin2 to the maker
+            let mut swap = TAKER_SWAPS.load(deps.storage, (channel_id, swap_id))?;
+            swap.status = SwapStatus::Completed;
+            TAKER_SWAPS.save(deps.storage, (channel_id, swap_id), &swap)?;
+
+            let msg = BankMsg::Send {
+                to_address: swap.maker_receiver,
+                amount: vec![swap.swap_info.coin2.coin],
+            };
+
+            Ok(IbcBasicResponse::new()
+                .add_message(msg)
+                .add_attribute("action", "ack_take_swap")
+                .add_attribute("swap_id", swap_id.to_string()))
+        }
+        SwapPacket::CancelSwap { swap_id } => {
+            // Counterparty closed the swap, refund coin1 to the maker
+            let mut swap = MAKER_SWAPS.load(deps.storage, swap_id)?;
+            if swap.status != SwapStatus::Cancelling {
+                // Already refunded on channel close
+                return Ok(IbcBasicResponse::new()
+                    .add_attribute("action", "ack_cancel_swap")
+                    .add_attribute("swap_id", swap_id.to_string()));
+            }
+            swap.status = SwapStatus::Cancelled;
+            MAKER_SWAPS.save(deps.storage, swap_id, &swap)?;
+            ESCROWED_SWAPS.remove(deps.storage, (channel_id, swap_id));
+
+            let msg = BankMsg::Send {
+                to_address: swap.maker.to_string(),
+                amount: vec![swap.swap_info.coin1.coin],
+            };
+
+            Ok(IbcBasicResponse::new()
+                .add_message(msg)
+                .add_attribute("action", "ack_cancel_swap")
+                .add_attribute("swap_id", swap_id.to_string()))
+        }
+    }
+}
+
+fn on_packet_failure(
+    deps: DepsMut,
+    packet: &IbcPacket,
+    err: String,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel_id = packet.src.channel_id.as_str();
+    let msg: SwapPacket = from_json(&packet.data)?;
+    match msg {
+        SwapPacket::MakeSwap { swap_id, .. } => {
+            // Swap never reached the counterparty, refund coin1 unless it already was
+            let mut swap = MAKER_SWAPS.load(deps.storage, swap_id)?;
+            if swap.status != SwapStatus::Open {
+                return Ok(IbcBasicResponse::new()
+                    .add_attribute("action", "refund_make_swap")
+                    .add_attribute("swap_id", swap_id.to_string())
+                    .add_attribute("error", err));
+            }
+            swap.status = SwapStatus::Failed;
+            MAKER_SWAPS.save(deps.storage, swap_id, &swap)?;
+            ESCROWED_SWAPS.remove(deps.storage, (channel_id, swap_id));
+
+            let msg = BankMsg::Send {
+                to_address: swap.maker.to_string(),
+                amount: vec![swap.swap_info.coin1.coin],
+            };
+
+            Ok(IbcBasicResponse::new()
+                .add_message(msg)
+                .add_attribute("action", "refund_make_swap")
+                .add_attribute("swap_id", swap_id.to_string())
+                .add_attribute("error", err))
+        }
+        SwapPacket::TakeSwap { swap_id, .. } => {
+            // Take was rejected, refund coin2 and reopen the swap
+            let mut swap = TAKER_SWAPS.load(deps.storage, (channel_id, swap_id))?;
+            let taker = swap.taker.take().ok_or(ContractError::SwapLocked {})?;
+            swap.taker_receiver = None;
+            swap.status = SwapStatus::Open;
+            TAKER_SWAPS.save(deps.storage, (channel_id, swap_id), &swap)?;
+
+            let msg = BankMsg::Send {
+                to_address: taker.to_string(),
+                amount: vec![swap.swap_info.coin2.coin],
+            };
+
+            Ok(IbcBasicResponse::new()
+                .add_message(msg)
+                .add_attribute("action", "refund_take_swap")
+                .add_attribute("swap_id", swap_id.to_string())
+                .add_attribute("error", err))
+        }
+        SwapPacket::CancelSwap { swap_id } => {
+            // Cancel was rejected, swap stays open unless it was refunded on channel close
+            let mut swap = MAKER_SWAPS.load(deps.storage, swap_id)?;
+            if swap.status == SwapStatus::Cancelling {
+                swap.status = SwapStatus::Open;
+                MAKER_SWAPS.save(deps.storage, swap_id, &swap)?;
+            }
+
+            Ok(IbcBasicResponse::new()
+                .add_attribute("action", "reopen_swap")
+                .add_attribute("swap_id", swap_id.to_string())
+                .add_attribute("error", err))
+        }
+    }
+}
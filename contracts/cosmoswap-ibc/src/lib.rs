@@ -0,0 +1,10 @@
+pub mod contract;
+mod error;
+pub mod ibc;
+pub mod msg;
+pub mod state;
+
+#[cfg(test)]
+mod tests;
+
+pub use crate::error::ContractError;
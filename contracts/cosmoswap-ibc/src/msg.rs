@@ -0,0 +1,67 @@
+use crate::state::{MakerSwap, TakerSwap};
+use cosmoswap_packages::types::SwapInfo;
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Binary, Timestamp};
+
+#[cw_serde]
+pub struct InstantiateMsg {}
+
+#[cw_serde]
+#[allow(clippy::large_enum_variant)]
+pub enum ExecuteMsg {
+    /// Escrows coin1 and sends the swap to the counterparty chain
+    MakeSwap {
+        channel_id: String,
+        swap_info: SwapInfo,
+        // Address on the counterparty chain that receives coin2
+        maker_receiver: String,
+        // Seconds until the swap can no longer be taken
+        timeout: u64,
+    },
+    /// Escrows coin2 and asks the maker chain to release coin1
+    TakeSwap {
+        channel_id: String,
+        swap_id: u64,
+        // Address on the maker chain that receives coin1
+        taker_receiver: String,
+    },
+    /// Asks the counterparty chain to close the swap and refunds coin1 on confirmation
+    CancelSwap { swap_id: u64 },
+    /// Returns coin1 to the maker once the swap expired, take packets can no longer arrive then
+    Reclaim { swap_id: u64 },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(MakerSwap)]
+    MakerSwap { swap_id: u64 },
+    #[returns(TakerSwap)]
+    TakerSwap { channel_id: String, swap_id: u64 },
+}
+
+// Packets sent between the two swap contracts
+#[cw_serde]
+#[allow(clippy::large_enum_variant)]
+pub enum SwapPacket {
+    MakeSwap {
+        swap_id: u64,
+        swap_info: SwapInfo,
+        maker_receiver: String,
+        expiration: Timestamp,
+    },
+    TakeSwap {
+        swap_id: u64,
+        taker_receiver: String,
+    },
+    CancelSwap {
+        swap_id: u64,
+    },
+}
+
+// Acknowledgement format used by ics20 and most ibc apps
+#[cw_serde]
+pub enum SwapAck {
+    Result(Binary),
+    Error(String),
+}
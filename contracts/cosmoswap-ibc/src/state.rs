@@ -0,0 +1,53 @@
+use cosmoswap_packages::types::SwapInfo;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Empty, Timestamp};
+use cw_storage_plus::{Item, Map};
+
+#[cw_serde]
+pub enum SwapStatus {
+    Open,
+    // Waiting for the counterparty to settle the take
+    Taking,
+    // Waiting for the counterparty to confirm the cancel
+    Cancelling,
+    Completed,
+    Cancelled,
+    // Make packet was rejected or timed out
+    Failed,
+    // coin1 was returned to the maker after expiration or on channel close
+    Refunded,
+}
+
+// Swap made on this chain, coin1 is escrowed here
+#[cw_serde]
+pub struct MakerSwap {
+    pub channel_id: String,
+    pub maker: Addr,
+    // Address on the counterparty chain that receives coin2
+    pub maker_receiver: String,
+    pub swap_info: SwapInfo,
+    pub expiration: Timestamp,
+    pub status: SwapStatus,
+}
+
+// Swap made on the counterparty chain, coin2 is escrowed here when taken
+#[cw_serde]
+pub struct TakerSwap {
+    pub maker_receiver: String,
+    pub swap_info: SwapInfo,
+    pub expiration: Timestamp,
+    pub taker: Option<Addr>,
+    // Address on the maker chain that receives coin1
+    pub taker_receiver: Option<String>,
+    pub status: SwapStatus,
+}
+
+// Open channels running the swap protocol
+pub const CHANNELS: Map<&str, Empty> = Map::new("channels");
+
+pub const SWAP_COUNT: Item<u64> = Item::new("swap_count");
+pub const MAKER_SWAPS: Map<u64, MakerSwap> = Map::new("maker_swaps");
+// Maker swaps still escrowing coin1 by channel id
+pub const ESCROWED_SWAPS: Map<(&str, u64), Empty> = Map::new("escrowed_swaps");
+// Keyed by local channel id and maker side swap id
+pub const TAKER_SWAPS: Map<(&str, u64), TakerSwap> = Map::new("taker_swaps");
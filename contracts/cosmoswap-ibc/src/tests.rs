@@ -0,0 +1,606 @@
+use crate::contract::{execute, instantiate, query};
+use crate::ibc::{
+    ibc_channel_close, ibc_channel_connect, ibc_channel_open, ibc_packet_ack, ibc_packet_receive,
+    ibc_packet_timeout, IBC_ORDER, IBC_VERSION,
+};
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, SwapAck, SwapPacket};
+use crate::state::{MakerSwap, SwapStatus, TakerSwap};
+use crate::ContractError;
+use cosmoswap_packages::types::{Arbiter, Recipient, SwapCoin, SwapInfo};
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_ibc_channel_close_init, mock_ibc_channel_connect_ack,
+    mock_ibc_channel_open_try, mock_ibc_packet_ack, mock_ibc_packet_recv, mock_ibc_packet_timeout,
+    mock_info, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{
+    coin, from_json, BankMsg, Binary, CosmosMsg, Decimal, IbcAcknowledgement, IbcMsg, IbcOrder,
+    OwnedDeps, SubMsg,
+};
+
+type Deps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+// Channel ids on the maker and taker chains
+const CHANNEL_A: &str = "channel-0";
+const CHANNEL_B: &str = "channel-7";
+
+const MAKER: &str = "maker";
+const MAKER_RECEIVER: &str = "maker_receiver";
+const TAKER: &str = "taker";
+const TAKER_RECEIVER: &str = "taker_receiver";
+
+fn setup_chain(channel_id: &str) -> Deps {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        InstantiateMsg {},
+    )
+    .unwrap();
+
+    let open = mock_ibc_channel_open_try(channel_id, IBC_ORDER, IBC_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap();
+    let connect = mock_ibc_channel_connect_ack(channel_id, IBC_ORDER, IBC_VERSION);
+    ibc_channel_connect(deps.as_mut(), mock_env(), connect).unwrap();
+    deps
+}
+
+fn swap_info() -> SwapInfo {
+    SwapInfo {
+        user1: MAKER.to_string(),
+        user2: TAKER.to_string(),
+        coin1: SwapCoin {
+            is_native: true,
+            coin: coin(1_000, "ujuno"),
+            cw20_address: None,
+        },
+        coin2: SwapCoin {
+            is_native: true,
+            coin: coin(5_000, "uosmo"),
+            cw20_address: None,
+        },
+        user1_recipient: None,
+        arbiter: None,
+        confirmation_window: None,
+        pricing: None,
+    }
+}
+
+fn sent_packet(msgs: &[SubMsg]) -> SwapPacket {
+    match &msgs[0].msg {
+        CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => from_json(data).unwrap(),
+        _ => panic!("expected a packet"),
+    }
+}
+
+fn success_ack() -> IbcAcknowledgement {
+    IbcAcknowledgement::encode_json(&SwapAck::Result(Binary::from(b"\x01"))).unwrap()
+}
+
+// Makes a swap on chain a and relays it to chain b
+fn make_swap(chain_a: &mut Deps, chain_b: &mut Deps) -> SwapPacket {
+    let msg = ExecuteMsg::MakeSwap {
+        channel_id: CHANNEL_A.to_string(),
+        swap_info: swap_info(),
+        maker_receiver: MAKER_RECEIVER.to_string(),
+        timeout: 600,
+    };
+    let res = execute(
+        chain_a.as_mut(),
+        mock_env(),
+        mock_info(MAKER, &[coin(1_000, "ujuno")]),
+        msg,
+    )
+    .unwrap();
+    let packet = sent_packet(&res.messages);
+
+    let recv = mock_ibc_packet_recv(CHANNEL_B, &packet).unwrap();
+    let res = ibc_packet_receive(chain_b.as_mut(), mock_env(), recv).unwrap();
+    let ack: SwapAck = from_json(res.acknowledgement).unwrap();
+    assert!(matches!(ack, SwapAck::Result(_)));
+
+    let ack = mock_ibc_packet_ack(CHANNEL_A, &packet, success_ack()).unwrap();
+    ibc_packet_ack(chain_a.as_mut(), mock_env(), ack).unwrap();
+    packet
+}
+
+fn take_swap(chain_b: &mut Deps) -> SwapPacket {
+    let msg = ExecuteMsg::TakeSwap {
+        channel_id: CHANNEL_B.to_string(),
+        swap_id: 1,
+        taker_receiver: TAKER_RECEIVER.to_string(),
+    };
+    let res = execute(
+        chain_b.as_mut(),
+        mock_env(),
+        mock_info(TAKER, &[coin(5_000, "uosmo")]),
+        msg,
+    )
+    .unwrap();
+    sent_packet(&res.messages)
+}
+
+fn maker_swap(deps: &Deps) -> MakerSwap {
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::MakerSwap { swap_id: 1 },
+    )
+    .unwrap();
+    from_json(res).unwrap()
+}
+
+fn taker_swap(deps: &Deps) -> TakerSwap {
+    let msg = QueryMsg::TakerSwap {
+        channel_id: CHANNEL_B.to_string(),
+        swap_id: 1,
+    };
+    from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+}
+
+mod channel {
+    use super::*;
+
+    #[test]
+    fn test_invalid_order() {
+        let mut deps = mock_dependencies();
+        let open = mock_ibc_channel_open_try(CHANNEL_A, IbcOrder::Ordered, IBC_VERSION);
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap_err();
+        assert_eq!(err, ContractError::InvalidChannelOrder { order: IBC_ORDER });
+    }
+
+    #[test]
+    fn test_invalid_version() {
+        let mut deps = mock_dependencies();
+        let open = mock_ibc_channel_open_try(CHANNEL_A, IBC_ORDER, "ics20-1");
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidChannelVersion {
+                got: "ics20-1".to_string(),
+                expected: IBC_VERSION.to_string()
+            }
+        );
+    }
+}
+
+mod make_swap {
+    use super::*;
+
+    #[test]
+    fn test_happy_path() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let mut chain_b = setup_chain(CHANNEL_B);
+        make_swap(&mut chain_a, &mut chain_b);
+
+        let swap = maker_swap(&chain_a);
+        assert_eq!(swap.status, SwapStatus::Open);
+        assert_eq!(swap.channel_id, CHANNEL_A);
+
+        let swap = taker_swap(&chain_b);
+        assert_eq!(swap.status, SwapStatus::Open);
+        assert_eq!(swap.swap_info, swap_info());
+        assert_eq!(swap.maker_receiver, MAKER_RECEIVER);
+    }
+
+    #[test]
+    fn test_unknown_channel() {
+        let mut deps = setup_chain(CHANNEL_A);
+        let msg = ExecuteMsg::MakeSwap {
+            channel_id: "channel-9".to_string(),
+            swap_info: swap_info(),
+            maker_receiver: MAKER_RECEIVER.to_string(),
+            timeout: 600,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MAKER, &[coin(1_000, "ujuno")]),
+            msg,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnknownChannel {
+                channel_id: "channel-9".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_cw20_coin() {
+        let mut deps = setup_chain(CHANNEL_A);
+        let mut info = swap_info();
+        info.coin2.is_native = false;
+        info.coin2.cw20_address = Some("token".to_string());
+        let msg = ExecuteMsg::MakeSwap {
+            channel_id: CHANNEL_A.to_string(),
+            swap_info: info,
+            maker_receiver: MAKER_RECEIVER.to_string(),
+            timeout: 600,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MAKER, &[coin(1_000, "ujuno")]),
+            msg,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidSwapCoin {});
+    }
+
+    #[test]
+    fn test_unsupported_swap_info() {
+        let mut deps = setup_chain(CHANNEL_A);
+        let mut with_user1_recipient = swap_info();
+        with_user1_recipient.user1_recipient = Some(Recipient::Address {
+            address: "recipient".to_string(),
+        });
+        let mut with_arbiter = swap_info();
+        with_arbiter.arbiter = Some(Arbiter {
+            address: "arbiter".to_string(),
+            fee_percentage: Decimal::percent(1),
+        });
+
+        for info in [with_user1_recipient, with_arbiter] {
+            let msg = ExecuteMsg::MakeSwap {
+                channel_id: CHANNEL_A.to_string(),
+                swap_info: info,
+                maker_receiver: MAKER_RECEIVER.to_string(),
+                timeout: 600,
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(MAKER, &[coin(1_000, "ujuno")]),
+                msg,
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::UnsupportedSwapInfo {});
+        }
+    }
+
+    #[test]
+    fn test_timeout_refund() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let msg = ExecuteMsg::MakeSwap {
+            channel_id: CHANNEL_A.to_string(),
+            swap_info: swap_info(),
+            maker_receiver: MAKER_RECEIVER.to_string(),
+            timeout: 600,
+        };
+        let res = execute(
+            chain_a.as_mut(),
+            mock_env(),
+            mock_info(MAKER, &[coin(1_000, "ujuno")]),
+            msg,
+        )
+        .unwrap();
+        let packet = sent_packet(&res.messages);
+
+        let timeout = mock_ibc_packet_timeout(CHANNEL_A, &packet).unwrap();
+        let res = ibc_packet_timeout(chain_a.as_mut(), mock_env(), timeout).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: MAKER.to_string(),
+                amount: vec![coin(1_000, "ujuno")]
+            })
+        );
+        assert_eq!(maker_swap(&chain_a).status, SwapStatus::Failed);
+    }
+}
+
+mod take_swap {
+    use super::*;
+
+    #[test]
+    fn test_happy_path() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let mut chain_b = setup_chain(CHANNEL_B);
+        make_swap(&mut chain_a, &mut chain_b);
+
+        let packet = take_swap(&mut chain_b);
+        assert_eq!(taker_swap(&chain_b).status, SwapStatus::Taking);
+
+        // Maker chain releases coin1 to the taker
+        let recv = mock_ibc_packet_recv(CHANNEL_A, &packet).unwrap();
+        let res = ibc_packet_receive(chain_a.as_mut(), mock_env(), recv).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: TAKER_RECEIVER.to_string(),
+                amount: vec![coin(1_000, "ujuno")]
+            })
+        );
+        assert_eq!(maker_swap(&chain_a).status, SwapStatus::Completed);
+
+        // Taker chain releases coin2 to the maker
+        let ack = mock_ibc_packet_ack(CHANNEL_B, &packet, success_ack()).unwrap();
+        let res = ibc_packet_ack(chain_b.as_mut(), mock_env(), ack).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: MAKER_RECEIVER.to_string(),
+                amount: vec![coin(5_000, "uosmo")]
+            })
+        );
+        assert_eq!(taker_swap(&chain_b).status, SwapStatus::Completed);
+    }
+
+    #[test]
+    fn test_invalid_taker() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let mut chain_b = setup_chain(CHANNEL_B);
+        make_swap(&mut chain_a, &mut chain_b);
+
+        let msg = ExecuteMsg::TakeSwap {
+            channel_id: CHANNEL_B.to_string(),
+            swap_id: 1,
+            taker_receiver: TAKER_RECEIVER.to_string(),
+        };
+        let err = execute(
+            chain_b.as_mut(),
+            mock_env(),
+            mock_info("random", &[coin(5_000, "uosmo")]),
+            msg,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_expired() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let mut chain_b = setup_chain(CHANNEL_B);
+        make_swap(&mut chain_a, &mut chain_b);
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(600);
+        let msg = ExecuteMsg::TakeSwap {
+            channel_id: CHANNEL_B.to_string(),
+            swap_id: 1,
+            taker_receiver: TAKER_RECEIVER.to_string(),
+        };
+        let err = execute(
+            chain_b.as_mut(),
+            env,
+            mock_info(TAKER, &[coin(5_000, "uosmo")]),
+            msg,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::SwapExpired {});
+    }
+
+    #[test]
+    fn test_rejected_refund() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let mut chain_b = setup_chain(CHANNEL_B);
+        make_swap(&mut chain_a, &mut chain_b);
+
+        // Maker starts cancelling before the take arrives
+        execute(
+            chain_a.as_mut(),
+            mock_env(),
+            mock_info(MAKER, &[]),
+            ExecuteMsg::CancelSwap { swap_id: 1 },
+        )
+        .unwrap();
+
+        let packet = take_swap(&mut chain_b);
+        let recv = mock_ibc_packet_recv(CHANNEL_A, &packet).unwrap();
+        let res = ibc_packet_receive(chain_a.as_mut(), mock_env(), recv).unwrap();
+        assert!(res.messages.is_empty());
+        let ack = IbcAcknowledgement::new(res.acknowledgement);
+
+        // Taker is refunded and the swap is open again
+        let ack = mock_ibc_packet_ack(CHANNEL_B, &packet, ack).unwrap();
+        let res = ibc_packet_ack(chain_b.as_mut(), mock_env(), ack).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: TAKER.to_string(),
+                amount: vec![coin(5_000, "uosmo")]
+            })
+        );
+        let swap = taker_swap(&chain_b);
+        assert_eq!(swap.status, SwapStatus::Open);
+        assert_eq!(swap.taker, None);
+    }
+}
+
+mod cancel_swap {
+    use super::*;
+
+    #[test]
+    fn test_happy_path() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let mut chain_b = setup_chain(CHANNEL_B);
+        make_swap(&mut chain_a, &mut chain_b);
+
+        let res = execute(
+            chain_a.as_mut(),
+            mock_env(),
+            mock_info(MAKER, &[]),
+            ExecuteMsg::CancelSwap { swap_id: 1 },
+        )
+        .unwrap();
+        let packet = sent_packet(&res.messages);
+        assert_eq!(maker_swap(&chain_a).status, SwapStatus::Cancelling);
+
+        let recv = mock_ibc_packet_recv(CHANNEL_B, &packet).unwrap();
+        ibc_packet_receive(chain_b.as_mut(), mock_env(), recv).unwrap();
+        assert_eq!(taker_swap(&chain_b).status, SwapStatus::Cancelled);
+
+        let ack = mock_ibc_packet_ack(CHANNEL_A, &packet, success_ack()).unwrap();
+        let res = ibc_packet_ack(chain_a.as_mut(), mock_env(), ack).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: MAKER.to_string(),
+                amount: vec![coin(1_000, "ujuno")]
+            })
+        );
+        assert_eq!(maker_swap(&chain_a).status, SwapStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_invalid_sender() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let mut chain_b = setup_chain(CHANNEL_B);
+        make_swap(&mut chain_a, &mut chain_b);
+
+        let err = execute(
+            chain_a.as_mut(),
+            mock_env(),
+            mock_info(TAKER, &[]),
+            ExecuteMsg::CancelSwap { swap_id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_rejected_after_take() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let mut chain_b = setup_chain(CHANNEL_B);
+        make_swap(&mut chain_a, &mut chain_b);
+        take_swap(&mut chain_b);
+
+        let res = execute(
+            chain_a.as_mut(),
+            mock_env(),
+            mock_info(MAKER, &[]),
+            ExecuteMsg::CancelSwap { swap_id: 1 },
+        )
+        .unwrap();
+        let packet = sent_packet(&res.messages);
+
+        let recv = mock_ibc_packet_recv(CHANNEL_B, &packet).unwrap();
+        let res = ibc_packet_receive(chain_b.as_mut(), mock_env(), recv).unwrap();
+        let ack = IbcAcknowledgement::new(res.acknowledgement);
+
+        let ack = mock_ibc_packet_ack(CHANNEL_A, &packet, ack).unwrap();
+        let res = ibc_packet_ack(chain_a.as_mut(), mock_env(), ack).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(maker_swap(&chain_a).status, SwapStatus::Open);
+    }
+}
+
+mod reclaim {
+    use super::*;
+
+    fn expired_env() -> cosmwasm_std::Env {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(600);
+        env
+    }
+
+    #[test]
+    fn test_happy_path() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let mut chain_b = setup_chain(CHANNEL_B);
+        make_swap(&mut chain_a, &mut chain_b);
+
+        let err = execute(
+            chain_a.as_mut(),
+            mock_env(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::Reclaim { swap_id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::SwapNotExpired {});
+
+        let res = execute(
+            chain_a.as_mut(),
+            expired_env(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::Reclaim { swap_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: MAKER.to_string(),
+                amount: vec![coin(1_000, "ujuno")]
+            })
+        );
+        assert_eq!(maker_swap(&chain_a).status, SwapStatus::Refunded);
+
+        let err = execute(
+            chain_a.as_mut(),
+            expired_env(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::Reclaim { swap_id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::SwapLocked {});
+    }
+
+    #[test]
+    fn test_completed_swap() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let mut chain_b = setup_chain(CHANNEL_B);
+        make_swap(&mut chain_a, &mut chain_b);
+        let packet = take_swap(&mut chain_b);
+
+        let recv = mock_ibc_packet_recv(CHANNEL_A, &packet).unwrap();
+        ibc_packet_receive(chain_a.as_mut(), mock_env(), recv).unwrap();
+
+        let err = execute(
+            chain_a.as_mut(),
+            expired_env(),
+            mock_info(MAKER, &[]),
+            ExecuteMsg::Reclaim { swap_id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::SwapLocked {});
+    }
+
+    #[test]
+    fn test_channel_close() {
+        let mut chain_a = setup_chain(CHANNEL_A);
+        let mut chain_b = setup_chain(CHANNEL_B);
+        make_swap(&mut chain_a, &mut chain_b);
+        make_swap(&mut chain_a, &mut chain_b);
+
+        // Second swap has a cancel in flight when the channel closes
+        let res = execute(
+            chain_a.as_mut(),
+            mock_env(),
+            mock_info(MAKER, &[]),
+            ExecuteMsg::CancelSwap { swap_id: 2 },
+        )
+        .unwrap();
+        let cancel = sent_packet(&res.messages);
+
+        let close = mock_ibc_channel_close_init(CHANNEL_A, IBC_ORDER, IBC_VERSION);
+        let res = ibc_channel_close(chain_a.as_mut(), mock_env(), close).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        for msg in res.messages {
+            assert_eq!(
+                msg.msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: MAKER.to_string(),
+                    amount: vec![coin(1_000, "ujuno")]
+                })
+            );
+        }
+        assert_eq!(maker_swap(&chain_a).status, SwapStatus::Refunded);
+
+        // Timed out cancel does not reopen the refunded swap
+        let timeout = mock_ibc_packet_timeout(CHANNEL_A, &cancel).unwrap();
+        let res = ibc_packet_timeout(chain_a.as_mut(), mock_env(), timeout).unwrap();
+        assert!(res.messages.is_empty());
+        let swap: MakerSwap = from_json(
+            query(
+                chain_a.as_ref(),
+                mock_env(),
+                QueryMsg::MakerSwap { swap_id: 2 },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(swap.status, SwapStatus::Refunded);
+    }
+}
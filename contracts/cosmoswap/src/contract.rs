@@ -1,20 +1,38 @@
-use cosmoswap_packages::funds::{check_single_coin, FundsError};
+use cosmoswap_packages::fees::{calculate_fee, fee_msg, FeeError};
+use cosmoswap_packages::funds::{
+    check_cw20_allowance, check_min_coin, check_single_coin, transfer_from_msg, transfer_msg,
+    FundsError,
+};
+use cosmoswap_packages::types::{FeeInfo, PauseInfo, Recipient, SwapCoin, SwapEvent};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{coin, from_binary, Attribute, BankMsg, CosmosMsg, WasmMsg};
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-use cw2::set_contract_version;
+use cosmwasm_std::{
+    coin, from_json, Attribute, BankMsg, CosmosMsg, Decimal, IbcMsg, IbcTimeout, Uint128, WasmMsg,
+};
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Coin, Deps, DepsMut, Env, HexBinary, MessageInfo, Response,
+    StdError, StdResult, Storage,
+};
+use cw2::{set_contract_version, CONTRACT as CONTRACT_INFO};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
-use std::ops::Mul;
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg};
-use crate::state::{Config, Swap, CONFIG, FEE_CONFIG, LOCK, SWAP};
+use crate::msg::{
+    ControllerExecuteMsg, ControllerQueryMsg, DisputeOutcome, ExecuteMsg, InstantiateMsg, QueryMsg,
+    ReceiveMsg, SimulateAcceptResponse,
+};
+use crate::state::{
+    Config, Dispute, Swap, SwapStatus, CONFIG, DISPUTE, FEE_CONFIG, IBC_TRANSFERS, STATUS, SWAP,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cosmoswap";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// cw2 contract name of the controller that instantiates swaps
+const CONTROLLER_CONTRACT_NAME: &str = "crates.io:cosmoswap-controller";
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -24,7 +42,15 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    // TODO: Make sure the sender is cosmoswap-controller contract
+    // Guards against instantiating swaps directly, it does not authenticate the controller since
+    // any contract can report the same cw2 name. Only swaps registered on the controller,
+    // which verifies the swap code on reply, are to be trusted.
+    let controller_info = CONTRACT_INFO
+        .query(&deps.querier, info.sender.clone())
+        .map_err(|_| ContractError::Unauthorized {})?;
+    if controller_info.contract != CONTROLLER_CONTRACT_NAME {
+        return Err(ContractError::Unauthorized {});
+    }
 
     if msg.swap_info.coin1.is_native {
         check_single_coin(&info, &msg.swap_info.coin1.coin)?;
@@ -33,6 +59,7 @@ pub fn instantiate(
     let config = Config {
         admin: info.sender,
         expiration: msg.expiration,
+        hash_lock: msg.hash_lock,
     };
     CONFIG.save(deps.storage, &config)?;
 
@@ -45,11 +72,15 @@ pub fn instantiate(
         user2,
         coin1: msg.swap_info.coin1,
         coin2: msg.swap_info.coin2,
+        user1_recipient: msg.swap_info.user1_recipient,
+        user2_recipient: None,
+        arbiter: msg.swap_info.arbiter,
+        confirmation_window: msg.swap_info.confirmation_window,
+        pricing: msg.swap_info.pricing,
     };
     SWAP.save(deps.storage, &swap)?;
 
-    // Set swap lock to false
-    LOCK.save(deps.storage, &false)?;
+    STATUS.save(deps.storage, &SwapStatus::Open)?;
 
     let mut attrs: Vec<Attribute> = vec![];
     if let Some(cw20_addr) = swap.coin1.cw20_address {
@@ -78,8 +109,15 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Accept {} => execute_accept(deps, env, info),
+        ExecuteMsg::Accept { recipient } => execute_accept(deps, env, info, recipient),
         ExecuteMsg::Cancel {} => execute_cancel(deps, env, info),
+        ExecuteMsg::Confirm {} => execute_confirm(deps, env, info),
+        ExecuteMsg::WithdrawDeposit {} => execute_withdraw_deposit(deps, env, info),
+        ExecuteMsg::Claim { preimage } => execute_claim(deps, env, info, preimage),
+        ExecuteMsg::Refund {} => execute_refund(deps, env, info),
+        ExecuteMsg::ClaimIbcRefund {} => execute_claim_ibc_refund(deps, env, info),
+        ExecuteMsg::RaiseDispute {} => execute_raise_dispute(deps, env, info),
+        ExecuteMsg::Resolve { outcome } => execute_resolve(deps, env, info, outcome),
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
     }
 }
@@ -88,209 +126,740 @@ pub fn execute_accept(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    recipient: Option<Recipient>,
+) -> Result<Response, ContractError> {
+    let taker = info.sender.clone();
+    accept_with_payment(deps, env, taker, Payment::Native(info), recipient)
+}
+
+/// Assets the taker pays coin2 with
+enum Payment {
+    Native(MessageInfo),
+    Cw20 { address: Addr, amount: Uint128 },
+}
+
+fn accept_with_payment(
+    deps: DepsMut,
+    env: Env,
+    taker: Addr,
+    payment: Payment,
+    recipient: Option<Recipient>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
+    if config.hash_lock.is_some() {
+        return Err(ContractError::InvalidSwapMode {});
+    }
     if config.expiration.is_expired(&env.block) {
         return Err(ContractError::SwapLocked {});
     };
 
-    // Return error if swap is locked by user1
-    let lock = LOCK.load(deps.storage)?;
-    if lock {
-        return Err(ContractError::SwapLocked {});
+    check_settlement_paused(deps.as_ref(), &config)?;
+
+    // Return error if swap is cancelled, settled or disputed
+    assert_open(deps.storage)?;
+
+    let mut swap = current_swap(SWAP.load(deps.storage)?, &env)?;
+
+    // Return error if the taker is not user2
+    if taker != swap.user2 {
+        return Err(ContractError::Unauthorized {});
+    };
+
+    // Recipient of user2 is only picked by user2, it replaces the one of a withdrawn deposit
+    validate_recipient(deps.as_ref(), &recipient, &swap.coin1)?;
+    SWAP.update(deps.storage, |mut swap| -> StdResult<_> {
+        swap.user2_recipient = recipient.clone();
+        Ok(swap)
+    })?;
+    swap.user2_recipient = recipient;
+
+    let msgs = collect_payment(deps.as_ref(), &env, &swap, payment)?;
+
+    // Two-phase swaps escrow coin2 until user1 confirms
+    if let Some(confirmation_window) = swap.confirmation_window {
+        let confirm_before = env.block.time.plus_seconds(confirmation_window);
+        STATUS.save(deps.storage, &SwapStatus::Deposited { confirm_before })?;
+        return Ok(Response::new()
+            .add_messages(msgs)
+            .add_message(notify_msg(&config.admin, SwapEvent::Deposited)?)
+            .add_attribute("action", "deposit")
+            .add_attribute("confirm_before", confirm_before.seconds().to_string()));
+    }
+
+    STATUS.save(deps.storage, &SwapStatus::Completed)?;
+    _accept(deps, &env, swap, msgs)
+}
+
+fn validate_recipient(
+    deps: Deps,
+    recipient: &Option<Recipient>,
+    swap_coin: &SwapCoin,
+) -> Result<(), ContractError> {
+    match recipient {
+        None => {}
+        Some(Recipient::Address { address }) | Some(Recipient::Contract { address, .. }) => {
+            deps.api.addr_validate(address)?;
+        }
+        // Proceeds can only be sent over IBC for native coins
+        Some(Recipient::Ibc(ibc_recipient))
+            if !swap_coin.is_native
+                || ibc_recipient.channel_id.is_empty()
+                || ibc_recipient.receiver.is_empty()
+                || ibc_recipient.timeout == 0 =>
+        {
+            return Err(ContractError::InvalidIbcRecipient {});
+        }
+        Some(Recipient::Ibc(_)) => {}
+    }
+    Ok(())
+}
+
+/// Returns the swap with coin2 set to the amount currently asked
+fn current_swap(mut swap: Swap, env: &Env) -> StdResult<Swap> {
+    if let Some(pricing) = &swap.pricing {
+        swap.coin2.coin.amount = pricing.current_amount(swap.coin2.coin.amount, env.block.time)?;
+    }
+    Ok(swap)
+}
+
+/// Returns error unless the swap can still be accepted, cancelled or disputed
+fn assert_open(storage: &dyn Storage) -> Result<(), ContractError> {
+    match STATUS.load(storage)? {
+        SwapStatus::Open => Ok(()),
+        SwapStatus::Disputed => Err(ContractError::SwapDisputed {}),
+        _ => Err(ContractError::SwapLocked {}),
     }
-    LOCK.save(deps.storage, &true)?;
+}
+
+pub fn execute_confirm(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    check_settlement_paused(deps.as_ref(), &config)?;
 
     let swap = SWAP.load(deps.storage)?;
+    if info.sender != swap.user1 {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match STATUS.load(deps.storage)? {
+        SwapStatus::Deposited { confirm_before } if env.block.time >= confirm_before => {
+            return Err(ContractError::ConfirmationExpired {});
+        }
+        SwapStatus::Deposited { .. } => {}
+        _ => return Err(ContractError::NoDeposit {}),
+    }
+    STATUS.save(deps.storage, &SwapStatus::Completed)?;
+
+    _accept(deps, &env, swap, vec![])
+}
 
-    // Return error if the sender is not user2
+pub fn execute_withdraw_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let swap = SWAP.load(deps.storage)?;
     if info.sender != swap.user2 {
         return Err(ContractError::Unauthorized {});
-    };
+    }
+
+    match STATUS.load(deps.storage)? {
+        SwapStatus::Deposited { confirm_before } if env.block.time < confirm_before => {
+            return Err(ContractError::ConfirmationPending {});
+        }
+        SwapStatus::Deposited { .. } => {}
+        _ => return Err(ContractError::NoDeposit {}),
+    }
+    // Swap can be accepted again or cancelled by user1
+    STATUS.save(deps.storage, &SwapStatus::Open)?;
+
+    let msg = transfer_msg(&swap.coin2, &swap.user2, swap.coin2.coin.amount)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_message(notify_msg(&config.admin, SwapEvent::Withdrawn)?)
+        .add_attribute("action", "withdraw_deposit"))
+}
 
-    check_single_coin(&info, &swap.coin2.coin.clone())?;
+/// Validates the payment against coin2 and returns the messages pulling it if needed
+fn collect_payment(
+    deps: Deps,
+    env: &Env,
+    swap: &Swap,
+    payment: Payment,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let coin2 = &swap.coin2;
+    match payment {
+        // Ask of dutch auctions keeps decaying so overpayments are refunded
+        Payment::Native(info) if coin2.is_native && swap.pricing.is_some() => {
+            let overpaid = check_min_coin(&info, &coin2.coin)?;
+            overpayment_msgs(swap, overpaid)
+        }
+        Payment::Native(info) if coin2.is_native => {
+            check_single_coin(&info, &coin2.coin)?;
+            Ok(vec![])
+        }
+        // cw20 coins are pulled with the allowance of user2 when no funds are sent
+        Payment::Native(info) => {
+            if let Some(sent_fund) = info.funds.first() {
+                return Err(FundsError::InvalidDenom {
+                    got: sent_fund.denom.to_string(),
+                    expected: coin2.coin.denom.to_string(),
+                }
+                .into());
+            }
+            check_cw20_allowance(
+                &deps.querier,
+                &env.block,
+                coin2,
+                &swap.user2,
+                &env.contract.address,
+            )?;
+            Ok(vec![transfer_from_msg(
+                coin2,
+                &swap.user2,
+                &env.contract.address,
+            )?])
+        }
+        Payment::Cw20 { address, amount } => {
+            if coin2.is_native || coin2.cw20_address.as_deref() != Some(address.as_str()) {
+                return Err(FundsError::InvalidDenom {
+                    got: address.to_string(),
+                    expected: coin2.asset(),
+                }
+                .into());
+            }
+            if amount < coin2.coin.amount || (swap.pricing.is_none() && amount != coin2.coin.amount)
+            {
+                return Err(FundsError::InvalidFunds {
+                    got: amount.to_string(),
+                    expected: coin2.coin.amount.to_string(),
+                }
+                .into());
+            }
+            overpayment_msgs(swap, amount.checked_sub(coin2.coin.amount)?)
+        }
+    }
+}
 
-    _accept(&deps, swap)
+fn overpayment_msgs(swap: &Swap, overpaid: Uint128) -> Result<Vec<CosmosMsg>, ContractError> {
+    if overpaid.is_zero() {
+        return Ok(vec![]);
+    }
+    Ok(vec![transfer_msg(&swap.coin2, &swap.user2, overpaid)?])
+}
+
+/// Settlement can be paused on the controller, cancelling is always allowed
+fn check_settlement_paused(deps: Deps, config: &Config) -> Result<(), ContractError> {
+    let pause_info: PauseInfo = deps
+        .querier
+        .query_wasm_smart(&config.admin, &ControllerQueryMsg::PauseInfo {})?;
+    if pause_info.settlement {
+        return Err(ContractError::SettlementPaused {});
+    }
+    Ok(())
 }
 
 pub fn execute_cancel(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
+    // Hashed time-locked swaps can only be refunded after expiration
+    let config = CONFIG.load(deps.storage)?;
+    if config.hash_lock.is_some() {
+        return Err(ContractError::InvalidSwapMode {});
+    }
+
     let swap = SWAP.load(deps.storage)?;
 
-    if info.sender != swap.user1 {
+    // Controller can sweep expired swaps back to user1
+    let swept = info.sender == config.admin && config.expiration.is_expired(&env.block);
+    if info.sender != swap.user1 && !swept {
         return Err(ContractError::Unauthorized {});
     };
 
-    let lock = LOCK.load(deps.storage)?;
-    if lock {
+    _cancel(deps, swap)
+}
+
+/// Claims are not paused since the preimage might already be revealed on the other chain
+pub fn execute_claim(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    preimage: HexBinary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let hash_lock = config.hash_lock.ok_or(ContractError::InvalidSwapMode {})?;
+    if config.expiration.is_expired(&env.block) {
         return Err(ContractError::SwapLocked {});
     }
 
-    _cancel(deps, swap)
+    assert_open(deps.storage)?;
+
+    if Sha256::digest(preimage.as_slice()).as_slice() != hash_lock.as_slice() {
+        return Err(ContractError::InvalidPreimage {});
+    }
+    STATUS.save(deps.storage, &SwapStatus::Completed)?;
+
+    let swap = SWAP.load(deps.storage)?;
+    let fee_config = FEE_CONFIG.load(deps.storage)?;
+    let coin1_fee = calculate_fee(&fee_config, &swap.coin1)?;
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if !coin1_fee.is_zero() {
+        msgs.push(fee_msg(&fee_config, &swap.coin1, coin1_fee)?);
+    }
+    msgs.push(settlement_msg(
+        deps.storage,
+        &env,
+        &swap.coin1,
+        &swap.user2,
+        &swap.user2_recipient,
+        swap.coin1.coin.amount.checked_sub(coin1_fee)?,
+    )?);
+
+    msgs.push(notify_msg(&config.admin, SwapEvent::Completed)?);
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "claim")
+        .add_attribute("preimage", preimage.to_hex()))
 }
 
-pub fn execute_receive(
+pub fn execute_refund(
     deps: DepsMut,
     env: Env,
     _info: MessageInfo,
-    cw20_receive_msg: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    let msg: ReceiveMsg = from_binary(&cw20_receive_msg.msg)?;
-    match msg {
-        ReceiveMsg::Accept {} => {
-            let config = CONFIG.load(deps.storage)?;
-            if config.expiration.is_expired(&env.block) {
-                return Err(ContractError::SwapLocked {});
-            };
+    let config = CONFIG.load(deps.storage)?;
+    if config.hash_lock.is_none() {
+        return Err(ContractError::InvalidSwapMode {});
+    }
+    if !config.expiration.is_expired(&env.block) {
+        return Err(ContractError::SwapNotExpired {});
+    }
 
-            let lock = LOCK.load(deps.storage)?;
-            if lock {
-                return Err(ContractError::SwapLocked {});
+    assert_open(deps.storage)?;
+    STATUS.save(deps.storage, &SwapStatus::Refunded)?;
+
+    let swap = SWAP.load(deps.storage)?;
+    let msg = transfer_msg(&swap.coin1, &swap.user1, swap.coin1.coin.amount)?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_message(notify_msg(&config.admin, SwapEvent::Refunded)?)
+        .add_attribute("action", "refund"))
+}
+
+pub fn execute_claim_ibc_refund(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let amount = IBC_TRANSFERS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoIbcTransfer {})?;
+
+    // IbcMsg::Transfer reports no outcome, so a refund is inferred from the balance since the
+    // denoms of both legs differ. Anyone sending the denom to the swap can make a delivered
+    // transfer claimable, the claim is capped at the recorded amount and can only be made once.
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &amount.denom)?;
+    if balance.amount < amount.amount {
+        return Err(ContractError::IbcTransferNotRefunded {});
+    }
+    IBC_TRANSFERS.remove(deps.storage, &info.sender);
+
+    let msg = BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![amount],
+    };
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "claim_ibc_refund"))
+}
+
+pub fn execute_raise_dispute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let swap = SWAP.load(deps.storage)?;
+    if swap.arbiter.is_none() {
+        return Err(ContractError::NoArbiter {});
+    }
+    if info.sender != swap.user1 && info.sender != swap.user2 {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Disputes can only be raised before coin2 is deposited
+    assert_open(deps.storage)?;
+    STATUS.save(deps.storage, &SwapStatus::Disputed)?;
+
+    DISPUTE.save(
+        deps.storage,
+        &Dispute {
+            raised_by: info.sender.clone(),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "raise_dispute")
+        .add_attribute("raised_by", info.sender))
+}
+
+pub fn execute_resolve(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    outcome: DisputeOutcome,
+) -> Result<Response, ContractError> {
+    let swap = SWAP.load(deps.storage)?;
+    let arbiter = swap.arbiter.clone().ok_or(ContractError::NoArbiter {})?;
+    if info.sender != arbiter.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    match STATUS.load(deps.storage)? {
+        SwapStatus::Disputed => {}
+        SwapStatus::Open => return Err(ContractError::NoDispute {}),
+        _ => return Err(ContractError::SwapLocked {}),
+    }
+    STATUS.save(deps.storage, &SwapStatus::Resolved)?;
+
+    // Arbiter fee is taken from coin1 before it is distributed
+    let amount = swap.coin1.coin.amount;
+    let arbiter_fee = amount.mul_floor(arbiter.fee_percentage);
+    let remaining = amount.checked_sub(arbiter_fee)?;
+    let user1_amount = match outcome {
+        DisputeOutcome::User1 => remaining,
+        DisputeOutcome::User2 => Uint128::zero(),
+        DisputeOutcome::Split { user1_share } => {
+            if user1_share > Decimal::one() {
+                return Err(ContractError::InvalidOutcome {});
             }
+            remaining.mul_floor(user1_share)
+        }
+    };
+    let user2_amount = remaining.checked_sub(user1_amount)?;
 
-            let swap = SWAP.load(deps.storage)?;
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if !arbiter_fee.is_zero() {
+        msgs.push(transfer_msg(&swap.coin1, &info.sender, arbiter_fee)?);
+    }
+    if !user1_amount.is_zero() {
+        msgs.push(transfer_msg(&swap.coin1, &swap.user1, user1_amount)?);
+    }
+    if !user2_amount.is_zero() {
+        msgs.push(settlement_msg(
+            deps.storage,
+            &env,
+            &swap.coin1,
+            &swap.user2,
+            &swap.user2_recipient,
+            user2_amount,
+        )?);
+    }
 
-            if cw20_receive_msg.sender != swap.user2 {
-                return Err(ContractError::Unauthorized {});
-            };
+    let config = CONFIG.load(deps.storage)?;
+    msgs.push(notify_msg(&config.admin, SwapEvent::Resolved)?);
 
-            if !swap.coin2.is_native {
-                if cw20_receive_msg.amount != swap.coin2.coin.amount {
-                    return Err(FundsError::InvalidFunds {
-                        got: cw20_receive_msg.amount.to_string(),
-                        expected: swap.coin2.coin.amount.to_string(),
-                    }
-                    .into());
-                };
-            };
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "resolve")
+        .add_attribute("arbiter_fee", arbiter_fee.to_string())
+        .add_attribute("user1_amount", user1_amount.to_string())
+        .add_attribute("user2_amount", user2_amount.to_string()))
+}
 
-            _accept(&deps, swap)
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_receive_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // The receive sender can only be trusted when forwarded by a cw20 of the swap
+    let swap = SWAP.load(deps.storage)?;
+    let cw20_coin = match swap_cw20(&swap, &info.sender) {
+        Some(swap_coin) => swap_coin.clone(),
+        None => return Err(ContractError::Unauthorized {}),
+    };
+
+    let msg: ReceiveMsg = from_json(&cw20_receive_msg.msg)?;
+    match msg {
+        ReceiveMsg::Accept { recipient } => {
+            let taker = deps.api.addr_validate(&cw20_receive_msg.sender)?;
+            let payment = Payment::Cw20 {
+                address: info.sender,
+                amount: cw20_receive_msg.amount,
+            };
+            accept_with_payment(deps, env, taker, payment, recipient)
+        }
+        ReceiveMsg::AcceptFor { taker, recipient } => {
+            let config = CONFIG.load(deps.storage)?;
+            if cw20_receive_msg.sender != config.admin {
+                return Err(ContractError::Unauthorized {});
+            }
+            let taker = deps.api.addr_validate(&taker)?;
+            let payment = Payment::Cw20 {
+                address: info.sender,
+                amount: cw20_receive_msg.amount,
+            };
+            accept_with_payment(deps, env, taker, payment, recipient)
         }
         ReceiveMsg::Cancel {} => {
-            let swap = SWAP.load(deps.storage)?;
+            let config = CONFIG.load(deps.storage)?;
+            if config.hash_lock.is_some() {
+                return Err(ContractError::InvalidSwapMode {});
+            }
 
             if cw20_receive_msg.sender != swap.user1 {
                 return Err(ContractError::Unauthorized {});
             };
 
-            _cancel(deps, swap)
+            // Tokens sent along with the cancel are returned to user1
+            let amount = cw20_receive_msg.amount;
+            let user1 = swap.user1.clone();
+            let res = _cancel(deps, swap)?;
+            if amount.is_zero() {
+                return Ok(res);
+            }
+            Ok(res.add_message(transfer_msg(&cw20_coin, &user1, amount)?))
         }
     }
 }
 
-fn _accept(deps: &DepsMut, swap: Swap) -> Result<Response, ContractError> {
+fn swap_cw20<'a>(swap: &'a Swap, contract: &Addr) -> Option<&'a SwapCoin> {
+    [&swap.coin1, &swap.coin2]
+        .into_iter()
+        .find(|c| c.cw20_address.as_deref() == Some(contract.as_str()))
+}
+
+fn _accept(
+    deps: DepsMut,
+    env: &Env,
+    swap: Swap,
+    mut msgs: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
     let fee_config = FEE_CONFIG.load(deps.storage)?;
 
     // Calculate swap fees
-    let coin1_fee = swap.coin1.coin.amount.mul(fee_config.percentage);
-    let coin2_fee = swap.coin2.coin.amount.mul(fee_config.percentage);
+    let (coin1_fee, coin2_fee) = calculate_swap_fees(&fee_config, &swap)?;
 
-    let mut msgs: Vec<CosmosMsg> = vec![];
+    if !coin1_fee.is_zero() {
+        msgs.push(fee_msg(&fee_config, &swap.coin1, coin1_fee)?);
+    }
 
-    if swap.coin1.is_native {
-        msgs.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: fee_config.payment_address.to_string(),
-            amount: vec![coin(coin1_fee.u128(), swap.coin1.coin.denom.clone())],
-        }));
-        msgs.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: swap.user2.to_string(),
-            amount: vec![coin(
-                swap.coin1.coin.amount.checked_sub(coin1_fee)?.u128(),
-                swap.coin1.coin.denom,
-            )],
-        }));
-    } else {
-        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: swap.coin1.cw20_address.as_ref().unwrap().to_string(),
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: fee_config.payment_address.to_string(),
-                amount: coin1_fee,
-            })?,
-            funds: vec![],
-        }));
-        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: swap.coin1.cw20_address.unwrap(),
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: swap.user2.to_string(),
-                amount: swap.coin1.coin.amount.checked_sub(coin1_fee)?,
-            })?,
-            funds: vec![],
-        }))
-    }
-
-    if swap.coin2.is_native {
-        msgs.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: fee_config.payment_address.to_string(),
-            amount: vec![coin(coin2_fee.u128(), swap.coin2.coin.denom.clone())],
-        }));
-        msgs.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: swap.user1.to_string(),
-            amount: vec![coin(
-                swap.coin2.coin.amount.checked_sub(coin2_fee)?.u128(),
-                swap.coin2.coin.denom,
-            )],
-        }));
-    } else {
-        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: swap.coin2.cw20_address.as_ref().unwrap().to_string(),
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: fee_config.payment_address.to_string(),
-                amount: coin2_fee,
-            })?,
-            funds: vec![],
-        }));
-        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: swap.coin2.cw20_address.unwrap(),
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: swap.user1.to_string(),
-                amount: swap.coin2.coin.amount.checked_sub(coin2_fee)?,
-            })?,
-            funds: vec![],
-        }))
+    msgs.push(settlement_msg(
+        deps.storage,
+        env,
+        &swap.coin1,
+        &swap.user2,
+        &swap.user2_recipient,
+        swap.coin1.coin.amount.checked_sub(coin1_fee)?,
+    )?);
+
+    if !coin2_fee.is_zero() {
+        msgs.push(fee_msg(&fee_config, &swap.coin2, coin2_fee)?);
     }
 
+    msgs.push(settlement_msg(
+        deps.storage,
+        env,
+        &swap.coin2,
+        &swap.user1,
+        &swap.user1_recipient,
+        swap.coin2.coin.amount.checked_sub(coin2_fee)?,
+    )?);
+
+    let config = CONFIG.load(deps.storage)?;
+    msgs.push(notify_msg(&config.admin, SwapEvent::Completed)?);
+
     Ok(Response::new()
         .add_messages(msgs)
         .add_attribute("action", "accept"))
 }
 
-fn _cancel(deps: DepsMut, swap: Swap) -> Result<Response, ContractError> {
-    LOCK.save(deps.storage, &true)?;
-
-    let msg: CosmosMsg;
+fn calculate_swap_fees(fee_config: &FeeInfo, swap: &Swap) -> Result<(Uint128, Uint128), FeeError> {
+    let coin1_fee = calculate_fee(fee_config, &swap.coin1)?;
+    let coin2_fee = calculate_fee(fee_config, &swap.coin2)?;
+    Ok((coin1_fee, coin2_fee))
+}
 
-    if swap.coin1.is_native {
-        msg = CosmosMsg::Bank(BankMsg::Send {
-            to_address: swap.user1.to_string(),
-            amount: vec![swap.coin1.coin.clone()],
-        });
-    } else {
-        msg = CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: swap.coin1.cw20_address.unwrap(),
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: swap.user1.to_string(),
-                amount: swap.coin1.coin.amount,
+/// Sends the proceeds of a user to the user or the recipient they picked
+fn settlement_msg(
+    storage: &mut dyn Storage,
+    env: &Env,
+    swap_coin: &SwapCoin,
+    user: &Addr,
+    recipient: &Option<Recipient>,
+    amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    let msg = match (recipient, swap_coin.is_native) {
+        (None, _) => transfer_msg(swap_coin, user, amount)?,
+        (Some(Recipient::Address { address }), _) => {
+            transfer_msg(swap_coin, &Addr::unchecked(address), amount)?
+        }
+        (Some(Recipient::Contract { address, msg }), true) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: address.to_string(),
+            msg: msg.clone(),
+            funds: vec![coin(amount.u128(), swap_coin.coin.denom.clone())],
+        }),
+        (Some(Recipient::Contract { address, msg }), false) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: swap_coin.cw20_address.as_ref().unwrap().to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                contract: address.to_string(),
+                amount,
+                msg: msg.clone(),
             })?,
             funds: vec![],
-        })
+        }),
+        (Some(Recipient::Ibc(ibc_recipient)), true) => {
+            let amount = coin(amount.u128(), swap_coin.coin.denom.clone());
+            // ICS-20 refunds timed out transfers to this contract
+            IBC_TRANSFERS.save(storage, user, &amount)?;
+            CosmosMsg::Ibc(IbcMsg::Transfer {
+                channel_id: ibc_recipient.channel_id.clone(),
+                to_address: ibc_recipient.receiver.clone(),
+                amount,
+                timeout: IbcTimeout::with_timestamp(
+                    env.block.time.plus_seconds(ibc_recipient.timeout),
+                ),
+            })
+        }
+        (Some(Recipient::Ibc(_)), false) => {
+            return Err(StdError::generic_err("IBC recipients need a native coin"))
+        }
+    };
+    Ok(msg)
+}
+
+/// Controller forwards swap events to its hook subscribers
+fn notify_msg(controller: &Addr, event: SwapEvent) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: controller.to_string(),
+        msg: to_json_binary(&ControllerExecuteMsg::NotifySwap { event })?,
+        funds: vec![],
+    }))
+}
+
+fn _cancel(deps: DepsMut, swap: Swap) -> Result<Response, ContractError> {
+    let mut msgs = vec![transfer_msg(
+        &swap.coin1,
+        &swap.user1,
+        swap.coin1.coin.amount,
+    )?];
+    // Cancelling a two-phase swap rejects the deposit of user2
+    if let SwapStatus::Deposited { .. } = STATUS.load(deps.storage)? {
+        msgs.push(transfer_msg(
+            &swap.coin2,
+            &swap.user2,
+            swap.coin2.coin.amount,
+        )?);
+    } else {
+        assert_open(deps.storage)?;
     }
+    STATUS.save(deps.storage, &SwapStatus::Cancelled)?;
+
+    let config = CONFIG.load(deps.storage)?;
 
     Ok(Response::new()
-        .add_message(msg)
+        .add_messages(msgs)
+        .add_message(notify_msg(&config.admin, SwapEvent::Cancelled)?)
         .add_attribute("action", "cancel"))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::Swap {} => to_binary(&query_swap(deps, env)?),
+        QueryMsg::Config {} => to_json_binary(&query_config(deps, env)?),
+        QueryMsg::Swap {} => to_json_binary(&query_swap(deps, env)?),
+        QueryMsg::Status {} => to_json_binary(&query_status(deps, env)?),
+        QueryMsg::SimulateAccept { sender } => {
+            to_json_binary(&query_simulate_accept(deps, env, sender)?)
+        }
+        QueryMsg::Dispute {} => to_json_binary(&query_dispute(deps, env)?),
+        QueryMsg::CurrentPrice {} => to_json_binary(&query_current_price(deps, env)?),
     }
 }
 
+fn query_config(deps: Deps, _env: Env) -> StdResult<Config> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(config)
+}
+
 fn query_swap(deps: Deps, _env: Env) -> StdResult<Swap> {
     let swap = SWAP.load(deps.storage)?;
     Ok(swap)
 }
+
+fn query_status(deps: Deps, _env: Env) -> StdResult<SwapStatus> {
+    STATUS.load(deps.storage)
+}
+
+fn query_dispute(deps: Deps, _env: Env) -> StdResult<Option<Dispute>> {
+    DISPUTE.may_load(deps.storage)
+}
+
+fn query_current_price(deps: Deps, env: Env) -> StdResult<Coin> {
+    let swap = current_swap(SWAP.load(deps.storage)?, &env)?;
+    Ok(swap.coin2.coin)
+}
+
+fn query_simulate_accept(
+    deps: Deps,
+    env: Env,
+    sender: String,
+) -> StdResult<SimulateAcceptResponse> {
+    let sender = deps.api.addr_validate(&sender)?;
+    let config = CONFIG.load(deps.storage)?;
+    let fee_config = FEE_CONFIG.load(deps.storage)?;
+    let swap = current_swap(SWAP.load(deps.storage)?, &env)?;
+    let lock = STATUS.load(deps.storage)? != SwapStatus::Open;
+    let pause_info: PauseInfo = deps
+        .querier
+        .query_wasm_smart(&config.admin, &ControllerQueryMsg::PauseInfo {})?;
+    let is_expired = config.expiration.is_expired(&env.block);
+
+    // Same checks as accept in the same order
+    let reason = if config.hash_lock.is_some() {
+        Some(ContractError::InvalidSwapMode {})
+    } else if is_expired {
+        Some(ContractError::SwapLocked {})
+    } else if pause_info.settlement {
+        Some(ContractError::SettlementPaused {})
+    } else if let Err(err) = assert_open(deps.storage) {
+        Some(err)
+    } else if sender != swap.user2 {
+        Some(ContractError::Unauthorized {})
+    } else {
+        None
+    };
+
+    let (coin1_fee, coin2_fee) = calculate_swap_fees(&fee_config, &swap)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(SimulateAcceptResponse {
+        user1_receives: coin(
+            swap.coin2.coin.amount.checked_sub(coin2_fee)?.u128(),
+            swap.coin2.coin.denom.clone(),
+        ),
+        user2_receives: coin(
+            swap.coin1.coin.amount.checked_sub(coin1_fee)?.u128(),
+            swap.coin1.coin.denom.clone(),
+        ),
+        coin1_fee: coin(coin1_fee.u128(), swap.coin1.coin.denom),
+        coin2_fee: coin(coin2_fee.u128(), swap.coin2.coin.denom),
+        is_expired,
+        is_paused: pause_info.settlement,
+        is_locked: lock,
+        is_authorized: reason.is_none(),
+        reason: reason.map(|err| err.to_string()),
+    })
+}
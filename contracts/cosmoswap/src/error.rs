@@ -1,3 +1,4 @@
+use cosmoswap_packages::fees::FeeError;
 use cosmoswap_packages::funds::FundsError;
 use cosmwasm_std::{OverflowError, StdError};
 use thiserror::Error;
@@ -10,9 +11,51 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Settlement is paused")]
+    SettlementPaused {},
+
     #[error("Swap is not active")]
     SwapLocked {},
 
+    #[error("Swap is not expired")]
+    SwapNotExpired {},
+
+    #[error("Operation is not supported for this swap mode")]
+    InvalidSwapMode {},
+
+    #[error("Preimage does not match the hash lock")]
+    InvalidPreimage {},
+
+    #[error("Swap has no deposit")]
+    NoDeposit {},
+
+    #[error("Confirmation window has passed")]
+    ConfirmationExpired {},
+
+    #[error("Confirmation window has not passed")]
+    ConfirmationPending {},
+
+    #[error("Swap has no arbiter")]
+    NoArbiter {},
+
+    #[error("Swap is disputed")]
+    SwapDisputed {},
+
+    #[error("Swap is not disputed")]
+    NoDispute {},
+
+    #[error("Split share must not be above 100%")]
+    InvalidOutcome {},
+
+    #[error("IBC recipients need a native coin, a channel, a receiver and a timeout")]
+    InvalidIbcRecipient {},
+
+    #[error("No IBC transfer found")]
+    NoIbcTransfer {},
+
+    #[error("IBC transfer is not refunded")]
+    IbcTransferNotRefunded {},
+
     #[error("Cw20 address is not valid")]
     InvalidCw20Addr {},
 
@@ -28,6 +71,9 @@ pub enum ContractError {
     #[error("{0}")]
     Funds(#[from] FundsError),
 
+    #[error("{0}")]
+    Fee(#[from] FeeError),
+
     #[error("{0}")]
     Owerflow(#[from] OverflowError),
 }
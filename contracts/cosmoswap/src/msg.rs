@@ -1,6 +1,7 @@
-use crate::state::Swap;
-use cosmoswap_packages::types::{FeeInfo, SwapInfo};
+use crate::state::{Config, Dispute, Swap, SwapStatus};
+use cosmoswap_packages::types::{FeeInfo, Recipient, SwapEvent, SwapInfo};
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Coin, Decimal, HexBinary};
 use cw20::{Cw20ReceiveMsg, Expiration};
 
 #[cw_serde]
@@ -8,24 +9,102 @@ pub struct InstantiateMsg {
     pub fee_info: FeeInfo,
     pub swap_info: SwapInfo,
     pub expiration: Expiration,
+    pub hash_lock: Option<HexBinary>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    Accept {},
+    /// Accepts the swap, the coin1 proceeds are sent to the recipient when set
+    Accept {
+        recipient: Option<Recipient>,
+    },
     Cancel {},
+    /// Settles a swap after user2 deposited coin2, only callable by user1
+    Confirm {},
+    /// Returns the deposit of user2 once the confirmation window has passed
+    WithdrawDeposit {},
+    /// Releases coin1 of a hashed time-locked swap to user2
+    Claim {
+        preimage: HexBinary,
+    },
+    /// Returns coin1 of an expired hashed time-locked swap to user1
+    Refund {},
+    /// Returns proceeds of a timed out or failed IBC transfer to the user, up to the sent amount
+    ClaimIbcRefund {},
+    /// Freezes the swap until the arbiter resolves it
+    RaiseDispute {},
+    /// Distributes coin1 of a disputed swap, only callable by the arbiter
+    Resolve {
+        outcome: DisputeOutcome,
+    },
     Receive(Cw20ReceiveMsg),
 }
 
+#[cw_serde]
+pub enum DisputeOutcome {
+    // coin1 is returned to user1
+    User1,
+    // coin1 is released to user2
+    User2,
+    // user1 receives the share of coin1 and user2 receives the rest
+    Split { user1_share: Decimal },
+}
+
 #[cw_serde]
 pub enum ReceiveMsg {
-    Accept {},
+    Accept {
+        recipient: Option<Recipient>,
+    },
     Cancel {},
+    /// Accepts the swap for the taker, tokens are forwarded by the controller
+    AcceptFor {
+        taker: String,
+        recipient: Option<Recipient>,
+    },
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
+    #[returns(Config)]
+    Config {},
     #[returns(Swap)]
     Swap {},
+    #[returns(SwapStatus)]
+    Status {},
+    #[returns(SimulateAcceptResponse)]
+    SimulateAccept { sender: String },
+    #[returns(Option<Dispute>)]
+    Dispute {},
+    /// Amount of coin2 currently asked, decays over time for dutch auctions
+    #[returns(Coin)]
+    CurrentPrice {},
+}
+
+#[cw_serde]
+pub struct SimulateAcceptResponse {
+    // Amount of coin2 user1 receives after fees
+    pub user1_receives: Coin,
+    // Amount of coin1 user2 receives after fees
+    pub user2_receives: Coin,
+    pub coin1_fee: Coin,
+    pub coin2_fee: Coin,
+    pub is_expired: bool,
+    pub is_paused: bool,
+    pub is_locked: bool,
+    pub is_authorized: bool,
+    // Why the sender can not accept the swap
+    pub reason: Option<String>,
+}
+
+// Queries sent to the cosmoswap-controller contract
+#[cw_serde]
+pub enum ControllerQueryMsg {
+    PauseInfo {},
+}
+
+// Messages sent to the cosmoswap-controller contract
+#[cw_serde]
+pub enum ControllerExecuteMsg {
+    NotifySwap { event: SwapEvent },
 }
@@ -1,13 +1,15 @@
-use cosmoswap_packages::types::{FeeInfo, SwapCoin};
+use cosmoswap_packages::types::{Arbiter, DutchAuction, FeeInfo, Recipient, SwapCoin};
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Coin, HexBinary, Timestamp};
 use cw20::Expiration;
-use cw_storage_plus::Item;
+use cw_storage_plus::{Item, Map};
 
 #[cw_serde]
 pub struct Config {
     pub admin: Addr,
     pub expiration: Expiration,
+    // Sha256 hash of the preimage for hashed time-locked swaps
+    pub hash_lock: Option<HexBinary>,
 }
 pub const CONFIG: Item<Config> = Item::new("config");
 
@@ -19,7 +21,35 @@ pub struct Swap {
     pub user2: Addr,
     pub coin1: SwapCoin,
     pub coin2: SwapCoin,
+    pub user1_recipient: Option<Recipient>,
+    // Set by user2 when accepting
+    pub user2_recipient: Option<Recipient>,
+    pub arbiter: Option<Arbiter>,
+    pub confirmation_window: Option<u64>,
+    pub pricing: Option<DutchAuction>,
 }
 pub const SWAP: Item<Swap> = Item::new("swap");
 
-pub const LOCK: Item<bool> = Item::new("lock");
+#[cw_serde]
+pub enum SwapStatus {
+    Open,
+    // coin2 is escrowed until user1 confirms, user2 can withdraw it after the deadline
+    Deposited { confirm_before: Timestamp },
+    Disputed,
+    Completed,
+    Cancelled,
+    Refunded,
+    Resolved,
+}
+pub const STATUS: Item<SwapStatus> = Item::new("status");
+
+// Disputed swaps are frozen until the arbiter resolves them
+#[cw_serde]
+pub struct Dispute {
+    pub raised_by: Addr,
+}
+pub const DISPUTE: Item<Dispute> = Item::new("dispute");
+
+// Proceeds sent over IBC, claimable once by the user if the transfer is refunded. Entries of
+// delivered transfers are kept since a refund can still be relayed late, at most one per user.
+pub const IBC_TRANSFERS: Map<&Addr, Coin> = Map::new("ibc_transfers");
@@ -1,40 +1,73 @@
-use cosmoswap_packages::types::SwapCoin;
-use cosmoswap_packages::types::{FeeInfo, SwapInfo};
-use cosmwasm_std::Uint128;
-use cosmwasm_std::{coin, Addr, Decimal, Empty};
-use cw20::Expiration;
-use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+use cosmoswap_packages::funds::FundsError;
+use cosmoswap_packages::types::{Arbiter, DutchAuction, IbcRecipient, PriceDecay};
+use cosmoswap_packages::types::{FeeBound, FeeDestination, FeeInfo, Rounding, SwapInfo};
+use cosmoswap_packages::types::{Recipient, SwapCoin};
+use cosmwasm_std::testing::MockStorage;
+use cosmwasm_std::{coin, Addr, Coin, Decimal, Empty, HexBinary};
+use cosmwasm_std::{to_json_binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128};
+use cw20::{BalanceResponse, Cw20Coin, Cw20QueryMsg, Cw20ReceiveMsg, Expiration};
+use cw20_base::msg::{ExecuteMsg as Cw20ExecuteMsg, InstantiateMsg as Cw20InstantiateMsg};
+use cw_multi_test::addons::{MockAddressGenerator, MockApiBech32};
+use cw_multi_test::error::AnyResult;
+use cw_multi_test::{
+    App, AppBuilder, AppResponse, BankKeeper, Contract, ContractWrapper, DistributionKeeper,
+    Executor, FailingModule, IbcAcceptingModule, StakeKeeper, WasmKeeper,
+};
+use sha2::{Digest, Sha256};
 use std::str::FromStr;
 
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::Swap;
+use cosmoswap_controller::msg::{
+    ExecuteMsg as ControllerExecuteMsg, InstantiateMsg as ControllerInstantiateMsg,
+};
+
+use crate::msg::{
+    DisputeOutcome, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, SimulateAcceptResponse,
+};
+use crate::state::{Dispute, Swap, SwapStatus};
 use crate::ContractError;
 
-const ADMIN: &str = "juno..admin";
-const USER1: &str = "juno..user1";
-const USER2: &str = "juno..user2";
+const ADMIN: &str = "juno1335hded4gyzpt00fpz75mms4m7ck02wgw07yhw9grahj4dzg4yvq4sfful";
+const USER1: &str = "juno1pgzph9rze2j2xxavx4n7pdhxlkgsq7rak245x0vk7mgh3j4le6gq2ltl4u";
+const USER2: &str = "juno1vqjarrly327529599rcc4qhzvhwe34pp5uyy4gylvxe5zupeqx3se0zcpp";
 const DENOM1: &str = "denom1";
 const DENOM2: &str = "denom2";
 
-fn mock_app() -> App {
-    AppBuilder::new().build(|router, _, storage| {
-        router
-            .bank
-            .init_balance(
-                storage,
-                &Addr::unchecked(USER1),
-                vec![coin(1_000_000, DENOM1)],
-            )
-            .unwrap();
-        router
-            .bank
-            .init_balance(
-                storage,
-                &Addr::unchecked(USER2),
-                vec![coin(1_000_000, DENOM2)],
-            )
-            .unwrap();
-    })
+// IBC transfers are accepted without moving funds, like a refunded transfer
+type TestApp = App<
+    BankKeeper,
+    MockApiBech32,
+    MockStorage,
+    FailingModule<Empty, Empty, Empty>,
+    WasmKeeper<Empty, Empty>,
+    StakeKeeper,
+    DistributionKeeper,
+    IbcAcceptingModule,
+>;
+
+// Bech32 addresses are needed for predicting instantiate2 addresses
+fn mock_app() -> TestApp {
+    AppBuilder::new()
+        .with_api(MockApiBech32::new("juno"))
+        .with_wasm(WasmKeeper::new().with_address_generator(MockAddressGenerator))
+        .with_ibc(IbcAcceptingModule::new())
+        .build(|router, _, storage| {
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &Addr::unchecked(USER1),
+                    vec![coin(1_000_000, DENOM1)],
+                )
+                .unwrap();
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &Addr::unchecked(USER2),
+                    vec![coin(1_000_000, DENOM2)],
+                )
+                .unwrap();
+        })
 }
 
 fn cosmoswap() -> Box<dyn Contract<Empty>> {
@@ -46,34 +79,209 @@ fn cosmoswap() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
-fn proper_instantiate(
-    app: &mut App,
-    fee_info: FeeInfo,
-    swap_info: SwapInfo,
-    expiration: Expiration,
-) -> Addr {
-    let code_id = app.store_code(cosmoswap());
-    let msg = InstantiateMsg {
-        fee_info,
-        swap_info: swap_info.clone(),
-        expiration,
+// Fee collector contract that accepts any funds sent with an empty hook message
+fn fee_collector() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::new())
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::new())
+        },
+        |_deps: Deps, _env: Env, _msg: Empty| -> StdResult<cosmwasm_std::Binary> {
+            to_json_binary(&Empty {})
+        },
+    );
+    Box::new(contract)
+}
+
+fn cosmoswap_controller() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cosmoswap_controller::contract::execute,
+        cosmoswap_controller::contract::instantiate,
+        cosmoswap_controller::contract::query,
+    )
+    .with_reply(cosmoswap_controller::contract::reply);
+    Box::new(contract)
+}
+
+fn cw20_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+// Both users hold the token so it can be used for either side of the swap
+fn setup_cw20_token(app: &mut TestApp, symbol: &str) -> Addr {
+    let cw20_code_id = app.store_code(cw20_contract());
+    let msg = Cw20InstantiateMsg {
+        name: "Test Token".to_string(),
+        symbol: symbol.to_string(),
+        decimals: 6,
+        initial_balances: vec![
+            Cw20Coin {
+                address: USER1.to_string(),
+                amount: Uint128::new(1_000_000),
+            },
+            Cw20Coin {
+                address: USER2.to_string(),
+                amount: Uint128::new(1_000_000),
+            },
+        ],
+        marketing: None,
+        mint: None,
     };
     app.instantiate_contract(
-        code_id,
-        Addr::unchecked(USER1),
+        cw20_code_id,
+        Addr::unchecked(ADMIN),
         &msg,
-        &vec![swap_info.coin1.coin],
-        "cosmoswap",
+        &[],
+        "test cw20",
         None,
     )
     .unwrap()
 }
 
+fn cw20_balance(app: &TestApp, cw20_addr: &Addr, address: &str) -> Uint128 {
+    let res: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            cw20_addr,
+            &Cw20QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    res.balance
+}
+
+// Swap contracts are created through the controller
+fn proper_instantiate(
+    app: &mut TestApp,
+    fee_info: FeeInfo,
+    swap_info: SwapInfo,
+    expiration: Expiration,
+) -> Addr {
+    instantiate_swap(app, fee_info, swap_info, expiration, None)
+}
+
+fn instantiate_swap(
+    app: &mut TestApp,
+    fee_info: FeeInfo,
+    swap_info: SwapInfo,
+    expiration: Expiration,
+    hash_lock: Option<HexBinary>,
+) -> Addr {
+    let cosmoswap_code_id = app.store_code(cosmoswap());
+    let cosmoswap_controller_code_id = app.store_code(cosmoswap_controller());
+    let msg = ControllerInstantiateMsg {
+        cosmoswap_code_id,
+        cosmoswap_version: "0.1.0".to_string(),
+        fee_percentage: fee_info.percentage,
+        fee_payment_address: fee_info.payment_address.to_string(),
+        fee_rounding: fee_info.rounding,
+        fee_bounds: fee_info.bounds,
+        fee_destination: fee_info.destination,
+    };
+    let cosmoswap_controller_addr = app
+        .instantiate_contract(
+            cosmoswap_controller_code_id,
+            Addr::unchecked(ADMIN),
+            &msg,
+            &[],
+            "cosmoswap-controller",
+            None,
+        )
+        .unwrap();
+
+    // cw20 coins are pulled by the controller with an allowance
+    let funds = if swap_info.coin1.is_native {
+        vec![swap_info.coin1.coin.clone()]
+    } else {
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            Addr::unchecked(swap_info.coin1.asset()),
+            &Cw20ExecuteMsg::IncreaseAllowance {
+                spender: cosmoswap_controller_addr.to_string(),
+                amount: swap_info.coin1.coin.amount,
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+        vec![]
+    };
+    let res = app
+        .execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr,
+            &ControllerExecuteMsg::CreateSwap {
+                swap_info,
+                expiration,
+                code_id: None,
+                hash_lock,
+            },
+            &funds,
+        )
+        .unwrap();
+    let swap_address = res
+        .events
+        .iter()
+        .filter(|e| e.ty == "wasm")
+        .flat_map(|e| &e.attributes)
+        .find(|attr| attr.key == "swap_address")
+        .unwrap();
+    Addr::unchecked(&swap_address.value)
+}
+
 mod instantiate {
     use super::*;
 
     #[test]
     fn test_happy_path() {
+        let mut app = mock_app();
+        let cosmoswap_addr = proper_instantiate(
+            &mut app,
+            FeeInfo {
+                percentage: Decimal::from_str("0.05").unwrap(),
+                payment_address: Addr::unchecked(ADMIN),
+                rounding: Rounding::Floor,
+                bounds: vec![],
+                destination: FeeDestination::Transfer,
+            },
+            SwapInfo {
+                user1: Addr::unchecked(USER1).to_string(),
+                user2: Addr::unchecked(USER2).to_string(),
+                coin1: SwapCoin {
+                    is_native: true,
+                    coin: coin(1_000, DENOM1),
+                    cw20_address: None,
+                },
+                coin2: SwapCoin {
+                    is_native: true,
+                    coin: coin(5_000, DENOM2),
+                    cw20_address: None,
+                },
+                user1_recipient: None,
+                arbiter: None,
+                confirmation_window: None,
+                pricing: None,
+            },
+            Expiration::Never {},
+        );
+        let res: Swap = app
+            .wrap()
+            .query_wasm_smart(cosmoswap_addr, &QueryMsg::Swap {})
+            .unwrap();
+        assert_eq!(res.user1, USER1);
+        assert_eq!(res.user2, USER2);
+    }
+
+    #[test]
+    fn test_invalid_sender() {
         let mut app = mock_app();
         let cosmoswap_code_id = app.store_code(cosmoswap());
 
@@ -81,6 +289,9 @@ mod instantiate {
             fee_info: FeeInfo {
                 percentage: Decimal::from_str("0.05").unwrap(),
                 payment_address: Addr::unchecked(ADMIN),
+                rounding: Rounding::Floor,
+                bounds: vec![],
+                destination: FeeDestination::Transfer,
             },
             swap_info: SwapInfo {
                 user1: Addr::unchecked(USER1).to_string(),
@@ -95,20 +306,29 @@ mod instantiate {
                     coin: coin(5_000, DENOM2),
                     cw20_address: None,
                 },
+                user1_recipient: None,
+                arbiter: None,
+                confirmation_window: None,
+                pricing: None,
             },
             expiration: Expiration::Never {},
+            hash_lock: None,
         };
-        let cosmoswap_addr = app
+        // Swaps can only be instantiated by the controller
+        let err = app
             .instantiate_contract(
                 cosmoswap_code_id,
                 Addr::unchecked(USER1),
                 &msg,
-                &vec![coin(1_000, DENOM1)],
+                &[coin(1_000, DENOM1)],
                 "cosmoswap",
                 None,
             )
-            .unwrap();
-        assert_eq!(cosmoswap_addr, "contract0");
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
     }
 }
 
@@ -126,6 +346,9 @@ mod execute {
                 FeeInfo {
                     percentage: Decimal::from_str("0.05").unwrap(),
                     payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
                 },
                 SwapInfo {
                     user1: Addr::unchecked(USER1).to_string(),
@@ -140,16 +363,20 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 },
                 Expiration::Never {},
             );
 
-            let msg = ExecuteMsg::Accept {};
+            let msg = ExecuteMsg::Accept { recipient: None };
             app.execute_contract(
                 Addr::unchecked(USER2),
                 cosmoswap_addr.clone(),
                 &msg,
-                &vec![coin(5_000, DENOM2)],
+                &[coin(5_000, DENOM2)],
             )
             .unwrap();
 
@@ -169,6 +396,9 @@ mod execute {
                 FeeInfo {
                     percentage: Decimal::from_str("0.05").unwrap(),
                     payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
                 },
                 SwapInfo {
                     user1: Addr::unchecked(USER1).to_string(),
@@ -183,14 +413,18 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 },
                 Expiration::AtHeight(expiration_height),
             );
             app.execute_contract(
                 Addr::unchecked(USER2),
                 cosmoswap_addr.clone(),
-                &ExecuteMsg::Accept {},
-                &vec![coin(5_000, DENOM2)],
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(5_000, DENOM2)],
             )
             .unwrap();
 
@@ -201,6 +435,9 @@ mod execute {
                 FeeInfo {
                     percentage: Decimal::from_str("0.05").unwrap(),
                     payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
                 },
                 SwapInfo {
                     user1: Addr::unchecked(USER1).to_string(),
@@ -215,26 +452,44 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 },
                 Expiration::AtTime(expiration_time),
             );
             app.execute_contract(
                 Addr::unchecked(USER2),
                 cosmoswap_addr.clone(),
-                &ExecuteMsg::Accept {},
-                &vec![coin(5_000, DENOM2)],
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(5_000, DENOM2)],
             )
             .unwrap();
         }
 
         #[test]
-        fn test_locked_swap() {
+        fn test_fee_bounds() {
             let mut app = mock_app();
             let cosmoswap_addr = proper_instantiate(
                 &mut app,
                 FeeInfo {
                     percentage: Decimal::from_str("0.05").unwrap(),
                     payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![
+                        FeeBound {
+                            asset: DENOM1.to_string(),
+                            min_fee: Some(Uint128::new(60)),
+                            max_fee: None,
+                        },
+                        FeeBound {
+                            asset: DENOM2.to_string(),
+                            min_fee: None,
+                            max_fee: Some(Uint128::new(200)),
+                        },
+                    ],
+                    destination: FeeDestination::Transfer,
                 },
                 SwapInfo {
                     user1: Addr::unchecked(USER1).to_string(),
@@ -249,26 +504,249 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 },
                 Expiration::Never {},
             );
 
-            let msg = ExecuteMsg::Cancel {};
             app.execute_contract(
-                Addr::unchecked(USER1),
+                Addr::unchecked(USER2),
+                cosmoswap_addr,
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap();
+
+            let res = app.wrap().query_balance(ADMIN, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(60));
+            let res = app.wrap().query_balance(ADMIN, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(200));
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(940));
+            let res = app.wrap().query_balance(USER1, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(4_800));
+        }
+
+        #[test]
+        fn test_fee_rounding() {
+            let mut app = mock_app();
+            let swap_info = SwapInfo {
+                user1: Addr::unchecked(USER1).to_string(),
+                user2: Addr::unchecked(USER2).to_string(),
+                coin1: SwapCoin {
+                    is_native: true,
+                    coin: coin(1_010, DENOM1),
+                    cw20_address: None,
+                },
+                coin2: SwapCoin {
+                    is_native: true,
+                    coin: coin(1_030, DENOM2),
+                    cw20_address: None,
+                },
+                user1_recipient: None,
+                arbiter: None,
+                confirmation_window: None,
+                pricing: None,
+            };
+
+            // 50.5 and 51.5 are rounded to the closest even number
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Bankers,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                swap_info.clone(),
+                Expiration::Never {},
+            );
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr,
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(1_030, DENOM2)],
+            )
+            .unwrap();
+
+            let res = app.wrap().query_balance(ADMIN, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(50));
+            let res = app.wrap().query_balance(ADMIN, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(52));
+
+            // 50.5 and 51.5 are rounded up
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Ceil,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                swap_info,
+                Expiration::Never {},
+            );
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr,
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(1_030, DENOM2)],
+            )
+            .unwrap();
+
+            let res = app.wrap().query_balance(ADMIN, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(50 + 51));
+            let res = app.wrap().query_balance(ADMIN, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(52 + 52));
+        }
+
+        #[test]
+        fn test_fee_destinations() {
+            let mut app = mock_app();
+            let swap_info = SwapInfo {
+                user1: Addr::unchecked(USER1).to_string(),
+                user2: Addr::unchecked(USER2).to_string(),
+                coin1: SwapCoin {
+                    is_native: true,
+                    coin: coin(1_000, DENOM1),
+                    cw20_address: None,
+                },
+                coin2: SwapCoin {
+                    is_native: true,
+                    coin: coin(5_000, DENOM2),
+                    cw20_address: None,
+                },
+                user1_recipient: None,
+                arbiter: None,
+                confirmation_window: None,
+                pricing: None,
+            };
+
+            // Fees are burned
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Burn,
+                },
+                swap_info.clone(),
+                Expiration::Never {},
+            );
+            app.execute_contract(
+                Addr::unchecked(USER2),
                 cosmoswap_addr.clone(),
-                &msg,
-                &vec![],
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap();
+
+            let res = app.wrap().query_balance(ADMIN, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::zero());
+            let res = app.wrap().query_balance(&cosmoswap_addr, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::zero());
+            let res = app.wrap().query_balance(&cosmoswap_addr, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::zero());
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(950));
+
+            // Fees are forwarded to a contract with the hook message
+            let fee_collector_code_id = app.store_code(fee_collector());
+            let fee_collector_addr = app
+                .instantiate_contract(
+                    fee_collector_code_id,
+                    Addr::unchecked(ADMIN),
+                    &Empty {},
+                    &[],
+                    "fee-collector",
+                    None,
+                )
+                .unwrap();
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: fee_collector_addr.clone(),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Hook {
+                        msg: to_json_binary(&Empty {}).unwrap(),
+                    },
+                },
+                swap_info,
+                Expiration::Never {},
+            );
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr,
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(5_000, DENOM2)],
             )
             .unwrap();
 
-            let msg = ExecuteMsg::Accept {};
+            let res = app
+                .wrap()
+                .query_balance(&fee_collector_addr, DENOM1)
+                .unwrap();
+            assert_eq!(res.amount, Uint128::new(50));
+            let res = app
+                .wrap()
+                .query_balance(&fee_collector_addr, DENOM2)
+                .unwrap();
+            assert_eq!(res.amount, Uint128::new(250));
+        }
+
+        #[test]
+        fn test_locked_swap() {
+            let mut app = mock_app();
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                SwapInfo {
+                    user1: Addr::unchecked(USER1).to_string(),
+                    user2: Addr::unchecked(USER2).to_string(),
+                    coin1: SwapCoin {
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                },
+                Expiration::Never {},
+            );
+
+            let msg = ExecuteMsg::Cancel {};
+            app.execute_contract(Addr::unchecked(USER1), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap();
+
+            let msg = ExecuteMsg::Accept { recipient: None };
             let err = app
                 .execute_contract(
                     Addr::unchecked(USER2),
                     cosmoswap_addr.clone(),
                     &msg,
-                    &vec![coin(5_000, DENOM2)],
+                    &[coin(5_000, DENOM2)],
                 )
                 .unwrap_err();
             assert_eq!(
@@ -285,6 +763,9 @@ mod execute {
                 FeeInfo {
                     percentage: Decimal::from_str("0.05").unwrap(),
                     payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
                 },
                 SwapInfo {
                     user1: Addr::unchecked(USER1).to_string(),
@@ -299,17 +780,21 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 },
                 Expiration::Never {},
             );
 
-            let msg = ExecuteMsg::Accept {};
+            let msg = ExecuteMsg::Accept { recipient: None };
             let err = app
                 .execute_contract(
                     Addr::unchecked(USER2),
                     cosmoswap_addr.clone(),
                     &msg,
-                    &vec![coin(5_000, DENOM2)],
+                    &[coin(5_000, DENOM2)],
                 )
                 .unwrap_err();
             assert_eq!(
@@ -322,7 +807,7 @@ mod execute {
         fn test_expired_swap() {
             let mut app = mock_app();
 
-            let new_expiration_height = Expiration::AtHeight(10);
+            let new_expiration_height = Expiration::AtHeight(app.block_info().height + 10);
             let new_expiration_time = Expiration::AtTime(app.block_info().time.plus_seconds(10));
 
             let cosmoswap_addr = proper_instantiate(
@@ -330,6 +815,9 @@ mod execute {
                 FeeInfo {
                     percentage: Decimal::from_str("0.05").unwrap(),
                     payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
                 },
                 SwapInfo {
                     user1: Addr::unchecked(USER1).to_string(),
@@ -344,6 +832,10 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 },
                 new_expiration_height,
             );
@@ -352,8 +844,8 @@ mod execute {
                 .execute_contract(
                     Addr::unchecked(USER2),
                     cosmoswap_addr.clone(),
-                    &ExecuteMsg::Accept {},
-                    &vec![coin(5_000, DENOM2)],
+                    &ExecuteMsg::Accept { recipient: None },
+                    &[coin(5_000, DENOM2)],
                 )
                 .unwrap_err();
             assert_eq!(
@@ -366,6 +858,9 @@ mod execute {
                 FeeInfo {
                     percentage: Decimal::from_str("0.05").unwrap(),
                     payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
                 },
                 SwapInfo {
                     user1: Addr::unchecked(USER1).to_string(),
@@ -380,6 +875,10 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 },
                 new_expiration_time,
             );
@@ -388,8 +887,8 @@ mod execute {
                 .execute_contract(
                     Addr::unchecked(USER2),
                     cosmoswap_addr.clone(),
-                    &ExecuteMsg::Accept {},
-                    &vec![coin(5_000, DENOM2)],
+                    &ExecuteMsg::Accept { recipient: None },
+                    &[coin(5_000, DENOM2)],
                 )
                 .unwrap_err();
             assert_eq!(
@@ -410,6 +909,9 @@ mod execute {
                 FeeInfo {
                     percentage: Decimal::from_str("0.05").unwrap(),
                     payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
                 },
                 SwapInfo {
                     user1: Addr::unchecked(USER1).to_string(),
@@ -424,6 +926,10 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 },
                 Expiration::Never {},
             );
@@ -432,25 +938,15 @@ mod execute {
             assert_eq!(res.amount, Uint128::new(999_000));
 
             let msg = ExecuteMsg::Cancel {};
-            app.execute_contract(
-                Addr::unchecked(USER1),
-                cosmoswap_addr.clone(),
-                &msg,
-                &vec![],
-            )
-            .unwrap();
+            app.execute_contract(Addr::unchecked(USER1), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap();
 
             let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
             assert_eq!(res.amount, Uint128::new(1_000_000));
 
-            let msg = ExecuteMsg::Accept {};
+            let msg = ExecuteMsg::Accept { recipient: None };
             let err = app
-                .execute_contract(
-                    Addr::unchecked(USER2),
-                    cosmoswap_addr.clone(),
-                    &msg,
-                    &vec![],
-                )
+                .execute_contract(Addr::unchecked(USER2), cosmoswap_addr.clone(), &msg, &[])
                 .unwrap_err();
             assert_eq!(
                 err.source().unwrap().to_string(),
@@ -466,6 +962,9 @@ mod execute {
                 FeeInfo {
                     percentage: Decimal::from_str("0.05").unwrap(),
                     payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
                 },
                 SwapInfo {
                     user1: Addr::unchecked(USER1).to_string(),
@@ -480,23 +979,1649 @@ mod execute {
                         coin: coin(5_000, DENOM2),
                         cw20_address: None,
                     },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
                 },
                 Expiration::Never {},
             );
 
             let msg = ExecuteMsg::Cancel {};
+            let err = app
+                .execute_contract(Addr::unchecked(USER2), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            )
+        }
+    }
+
+    mod payment {
+        use super::*;
+
+        fn native_coin(amount: u128, denom: &str) -> SwapCoin {
+            SwapCoin {
+                is_native: true,
+                coin: coin(amount, denom),
+                cw20_address: None,
+            }
+        }
+
+        fn cw20_coin(amount: u128, symbol: &str, cw20_addr: &Addr) -> SwapCoin {
+            SwapCoin {
+                is_native: false,
+                coin: coin(amount, symbol),
+                cw20_address: Some(cw20_addr.to_string()),
+            }
+        }
+
+        fn payment_instantiate(app: &mut TestApp, coin1: SwapCoin, coin2: SwapCoin) -> Addr {
+            proper_instantiate(
+                app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1,
+                    coin2,
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                },
+                Expiration::Never {},
+            )
+        }
+
+        fn send_accept(
+            app: &mut TestApp,
+            cw20_addr: &Addr,
+            cosmoswap_addr: &Addr,
+            amount: u128,
+        ) -> AnyResult<AppResponse> {
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cw20_addr.clone(),
+                &Cw20ExecuteMsg::Send {
+                    contract: cosmoswap_addr.to_string(),
+                    amount: Uint128::new(amount),
+                    msg: to_json_binary(&ReceiveMsg::Accept { recipient: None }).unwrap(),
+                },
+                &[],
+            )
+        }
+
+        // Accepted swaps are locked for every payment path
+        fn assert_locked(app: &mut TestApp, cosmoswap_addr: &Addr) {
             let err = app
                 .execute_contract(
-                    Addr::unchecked(USER2),
+                    Addr::unchecked(USER1),
                     cosmoswap_addr.clone(),
-                    &msg,
-                    &vec![],
+                    &ExecuteMsg::Cancel {},
+                    &[],
                 )
                 .unwrap_err();
             assert_eq!(
                 err.source().unwrap().to_string(),
-                ContractError::Unauthorized {}.to_string()
-            )
+                ContractError::SwapLocked {}.to_string()
+            );
+        }
+
+        #[test]
+        fn test_native_native() {
+            let mut app = mock_app();
+            let cw20_addr = setup_cw20_token(&mut app, "teto");
+            let cosmoswap_addr = payment_instantiate(
+                &mut app,
+                native_coin(1_000, DENOM1),
+                native_coin(5_000, DENOM2),
+            );
+
+            // cw20 tokens that are not part of the swap are rejected
+            let err = send_accept(&mut app, &cw20_addr, &cosmoswap_addr, 5_000).unwrap_err();
+            assert_eq!(
+                err.source().unwrap().source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Accept { recipient: None },
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Funds(FundsError::MissingFunds {}).to_string()
+            );
+
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap();
+
+            let res = app.wrap().query_balance(USER1, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(4_750));
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(950));
+            assert_locked(&mut app, &cosmoswap_addr);
+        }
+
+        #[test]
+        fn test_native_cw20() {
+            let mut app = mock_app();
+            let cw20_addr = setup_cw20_token(&mut app, "teto");
+            let other_cw20_addr = setup_cw20_token(&mut app, "teto");
+            let cosmoswap_addr = payment_instantiate(
+                &mut app,
+                native_coin(1_000, DENOM1),
+                cw20_coin(5_000, "teto", &cw20_addr),
+            );
+
+            // Tokens of another cw20 contract are rejected
+            let err = send_accept(&mut app, &other_cw20_addr, &cosmoswap_addr, 5_000).unwrap_err();
+            assert_eq!(
+                err.source().unwrap().source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            let err = send_accept(&mut app, &cw20_addr, &cosmoswap_addr, 4_000).unwrap_err();
+            assert_eq!(
+                err.source().unwrap().source().unwrap().to_string(),
+                ContractError::Funds(FundsError::InvalidFunds {
+                    got: "4000".to_string(),
+                    expected: "5000".to_string(),
+                })
+                .to_string()
+            );
+
+            // Native funds are not accepted for a cw20 coin
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Accept { recipient: None },
+                    &[coin(5_000, DENOM2)],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Funds(FundsError::InvalidDenom {
+                    got: DENOM2.to_string(),
+                    expected: "teto".to_string(),
+                })
+                .to_string()
+            );
+
+            send_accept(&mut app, &cw20_addr, &cosmoswap_addr, 5_000).unwrap();
+
+            assert_eq!(
+                cw20_balance(&app, &cw20_addr, USER1),
+                Uint128::new(1_004_750)
+            );
+            assert_eq!(cw20_balance(&app, &cw20_addr, ADMIN), Uint128::new(250));
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(950));
+            assert_locked(&mut app, &cosmoswap_addr);
+        }
+
+        #[test]
+        fn test_cw20_native() {
+            let mut app = mock_app();
+            let cw20_addr = setup_cw20_token(&mut app, "teto");
+            let cosmoswap_addr = payment_instantiate(
+                &mut app,
+                cw20_coin(1_000, "teto", &cw20_addr),
+                native_coin(5_000, DENOM2),
+            );
+            assert_eq!(
+                cw20_balance(&app, &cw20_addr, cosmoswap_addr.as_str()),
+                Uint128::new(1_000)
+            );
+
+            let err = send_accept(&mut app, &cw20_addr, &cosmoswap_addr, 5_000).unwrap_err();
+            assert_eq!(
+                err.source().unwrap().source().unwrap().to_string(),
+                ContractError::Funds(FundsError::InvalidDenom {
+                    got: cw20_addr.to_string(),
+                    expected: DENOM2.to_string(),
+                })
+                .to_string()
+            );
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Accept { recipient: None },
+                    &[coin(4_000, DENOM2)],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Funds(FundsError::InvalidFunds {
+                    got: "4000".to_string(),
+                    expected: "5000".to_string(),
+                })
+                .to_string()
+            );
+
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap();
+
+            let res = app.wrap().query_balance(USER1, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(4_750));
+            assert_eq!(
+                cw20_balance(&app, &cw20_addr, USER2),
+                Uint128::new(1_000_950)
+            );
+            assert_eq!(cw20_balance(&app, &cw20_addr, ADMIN), Uint128::new(50));
+            assert_locked(&mut app, &cosmoswap_addr);
+        }
+
+        #[test]
+        fn test_cw20_cw20() {
+            let mut app = mock_app();
+            let cw20_addr1 = setup_cw20_token(&mut app, "teto");
+            let cw20_addr2 = setup_cw20_token(&mut app, "miku");
+            let cosmoswap_addr = payment_instantiate(
+                &mut app,
+                cw20_coin(1_000, "teto", &cw20_addr1),
+                cw20_coin(5_000, "miku", &cw20_addr2),
+            );
+
+            // coin1 tokens can not pay for coin2
+            let err = send_accept(&mut app, &cw20_addr1, &cosmoswap_addr, 5_000).unwrap_err();
+            assert_eq!(
+                err.source().unwrap().source().unwrap().to_string(),
+                ContractError::Funds(FundsError::InvalidDenom {
+                    got: cw20_addr1.to_string(),
+                    expected: cw20_addr2.to_string(),
+                })
+                .to_string()
+            );
+
+            send_accept(&mut app, &cw20_addr2, &cosmoswap_addr, 5_000).unwrap();
+
+            assert_eq!(
+                cw20_balance(&app, &cw20_addr2, USER1),
+                Uint128::new(1_004_750)
+            );
+            assert_eq!(
+                cw20_balance(&app, &cw20_addr1, USER2),
+                Uint128::new(1_000_950)
+            );
+            assert_eq!(cw20_balance(&app, &cw20_addr1, ADMIN), Uint128::new(50));
+            assert_eq!(cw20_balance(&app, &cw20_addr2, ADMIN), Uint128::new(250));
+            assert_locked(&mut app, &cosmoswap_addr);
+        }
+
+        // Tokens sent along with a cw20 cancel are returned to user1
+        #[test]
+        fn test_cw20_cancel() {
+            let mut app = mock_app();
+            let cw20_addr = setup_cw20_token(&mut app, "teto");
+            let cosmoswap_addr = payment_instantiate(
+                &mut app,
+                cw20_coin(1_000, "teto", &cw20_addr),
+                native_coin(5_000, DENOM2),
+            );
+            assert_eq!(cw20_balance(&app, &cw20_addr, USER1), Uint128::new(999_000));
+
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cw20_addr.clone(),
+                &Cw20ExecuteMsg::Send {
+                    contract: cosmoswap_addr.to_string(),
+                    amount: Uint128::new(10),
+                    msg: to_json_binary(&ReceiveMsg::Cancel {}).unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+
+            assert_eq!(
+                cw20_balance(&app, &cw20_addr, USER1),
+                Uint128::new(1_000_000)
+            );
+            assert_eq!(
+                cw20_balance(&app, &cw20_addr, cosmoswap_addr.as_str()),
+                Uint128::zero()
+            );
+        }
+
+        // Direct receive messages from an address that is not a cw20 contract are rejected
+        #[test]
+        fn test_spoofed_receive() {
+            let mut app = mock_app();
+            let cw20_addr = setup_cw20_token(&mut app, "teto");
+            let cosmoswap_addr = payment_instantiate(
+                &mut app,
+                native_coin(1_000, DENOM1),
+                cw20_coin(5_000, "teto", &cw20_addr),
+            );
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Receive(Cw20ReceiveMsg {
+                        sender: USER2.to_string(),
+                        amount: Uint128::new(5_000),
+                        msg: to_json_binary(&ReceiveMsg::Accept { recipient: None }).unwrap(),
+                    }),
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            // Cancel can not be spoofed on behalf of user1
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Receive(Cw20ReceiveMsg {
+                        sender: USER1.to_string(),
+                        amount: Uint128::zero(),
+                        msg: to_json_binary(&ReceiveMsg::Cancel {}).unwrap(),
+                    }),
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+            assert_eq!(
+                app.wrap()
+                    .query_wasm_smart::<SwapStatus>(&cosmoswap_addr, &QueryMsg::Status {})
+                    .unwrap(),
+                SwapStatus::Open
+            );
+        }
+    }
+
+    mod recipient {
+        use super::*;
+
+        fn recipient_swap_info(user1_recipient: Option<Recipient>) -> SwapInfo {
+            SwapInfo {
+                user1: Addr::unchecked(USER1).to_string(),
+                user2: Addr::unchecked(USER2).to_string(),
+                coin1: SwapCoin {
+                    is_native: true,
+                    coin: coin(1_000, DENOM1),
+                    cw20_address: None,
+                },
+                coin2: SwapCoin {
+                    is_native: true,
+                    coin: coin(5_000, DENOM2),
+                    cw20_address: None,
+                },
+                user1_recipient,
+                arbiter: None,
+                confirmation_window: None,
+                pricing: None,
+            }
+        }
+
+        fn ibc_recipient() -> Recipient {
+            Recipient::Ibc(IbcRecipient {
+                channel_id: "channel-0".to_string(),
+                receiver: "osmo1receiver".to_string(),
+                timeout: 600,
+            })
+        }
+
+        #[test]
+        fn test_address_recipient() {
+            let mut app = mock_app();
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                recipient_swap_info(Some(Recipient::Address {
+                    address: ADMIN.to_string(),
+                })),
+                Expiration::Never {},
+            );
+
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr,
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap();
+
+            // Admin receives both the proceeds and the fee of user1
+            let res = app.wrap().query_balance(ADMIN, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(5_000));
+            let res = app.wrap().query_balance(USER1, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::zero());
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(950));
+        }
+
+        #[test]
+        fn test_contract_recipient() {
+            let mut app = mock_app();
+            let vault_code_id = app.store_code(fee_collector());
+            let vault_addr = app
+                .instantiate_contract(
+                    vault_code_id,
+                    Addr::unchecked(ADMIN),
+                    &Empty {},
+                    &[],
+                    "vault",
+                    None,
+                )
+                .unwrap();
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                recipient_swap_info(None),
+                Expiration::Never {},
+            );
+
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr,
+                &ExecuteMsg::Accept {
+                    recipient: Some(Recipient::Contract {
+                        address: vault_addr.to_string(),
+                        msg: to_json_binary(&Empty {}).unwrap(),
+                    }),
+                },
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap();
+
+            let res = app.wrap().query_balance(&vault_addr, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(950));
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::zero());
+            let res = app.wrap().query_balance(USER1, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(4_750));
+        }
+
+        // Recipient of user2 is validated when accepting
+        #[test]
+        fn test_invalid_recipient() {
+            let mut app = mock_app();
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                recipient_swap_info(None),
+                Expiration::Never {},
+            );
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Accept {
+                        recipient: Some(Recipient::Ibc(IbcRecipient {
+                            channel_id: "channel-0".to_string(),
+                            receiver: "osmo1receiver".to_string(),
+                            timeout: 0,
+                        })),
+                    },
+                    &[coin(5_000, DENOM2)],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::InvalidIbcRecipient {}.to_string()
+            );
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr,
+                    &ExecuteMsg::Accept {
+                        recipient: Some(Recipient::Address {
+                            address: "invalid".to_string(),
+                        }),
+                    },
+                    &[coin(5_000, DENOM2)],
+                )
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Std(_)
+            ));
+        }
+
+        #[test]
+        fn test_claim_ibc_refund() {
+            let mut app = mock_app();
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                recipient_swap_info(None),
+                Expiration::Never {},
+            );
+
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::Accept {
+                    recipient: Some(ibc_recipient()),
+                },
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap();
+
+            // Local leg is settled, IBC leg stays in the contract
+            let res = app.wrap().query_balance(USER1, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(4_750));
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::zero());
+
+            let msg = ExecuteMsg::ClaimIbcRefund {};
+            let err = app
+                .execute_contract(Addr::unchecked(USER1), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::NoIbcTransfer {}.to_string()
+            );
+
+            app.execute_contract(Addr::unchecked(USER2), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap();
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(950));
+
+            let err = app
+                .execute_contract(Addr::unchecked(USER2), cosmoswap_addr, &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::NoIbcTransfer {}.to_string()
+            );
+        }
+
+        #[test]
+        fn test_claim_after_donation() {
+            let mut app = mock_app();
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                recipient_swap_info(None),
+                Expiration::Never {},
+            );
+
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::Accept {
+                    recipient: Some(ibc_recipient()),
+                },
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap();
+
+            // Transfer is delivered, ICS-20 escrows the proceeds
+            app.init_modules(|router, _, storage| {
+                router
+                    .bank
+                    .init_balance(storage, &cosmoswap_addr, vec![])
+                    .unwrap()
+            });
+
+            let msg = ExecuteMsg::ClaimIbcRefund {};
+            let err = app
+                .execute_contract(Addr::unchecked(USER2), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::IbcTransferNotRefunded {}.to_string()
+            );
+
+            // Donation can only be claimed up to the sent amount and once
+            app.send_tokens(
+                Addr::unchecked(USER1),
+                cosmoswap_addr.clone(),
+                &[coin(2_000, DENOM1)],
+            )
+            .unwrap();
+            app.execute_contract(Addr::unchecked(USER2), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap();
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(950));
+
+            let err = app
+                .execute_contract(Addr::unchecked(USER2), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::NoIbcTransfer {}.to_string()
+            );
+            let res = app.wrap().query_balance(&cosmoswap_addr, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(1_050));
+        }
+    }
+
+    mod dispute {
+        use super::*;
+
+        fn dispute_instantiate(app: &mut TestApp, arbiter: Option<Arbiter>) -> Addr {
+            proper_instantiate(
+                app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter,
+                    confirmation_window: None,
+                    pricing: None,
+                },
+                Expiration::Never {},
+            )
+        }
+
+        fn arbiter(app: &TestApp) -> Arbiter {
+            Arbiter {
+                address: app.api().addr_make("arbiter").to_string(),
+                fee_percentage: Decimal::from_str("0.1").unwrap(),
+            }
+        }
+
+        #[test]
+        fn test_split() {
+            let mut app = mock_app();
+            let arbiter = arbiter(&app);
+            let cosmoswap_addr = dispute_instantiate(&mut app, Some(arbiter.clone()));
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::RaiseDispute {},
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            // Only disputed swaps can be resolved
+            let msg = ExecuteMsg::Resolve {
+                outcome: DisputeOutcome::Split {
+                    user1_share: Decimal::percent(50),
+                },
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(&arbiter.address),
+                    cosmoswap_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::NoDispute {}.to_string()
+            );
+
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::RaiseDispute {},
+                &[],
+            )
+            .unwrap();
+            let res: Option<Dispute> = app
+                .wrap()
+                .query_wasm_smart(cosmoswap_addr.clone(), &QueryMsg::Dispute {})
+                .unwrap();
+            assert_eq!(
+                res,
+                Some(Dispute {
+                    raised_by: Addr::unchecked(USER2)
+                })
+            );
+
+            // Disputed swaps are frozen
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Accept { recipient: None },
+                    &[coin(5_000, DENOM2)],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::SwapDisputed {}.to_string()
+            );
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Cancel {},
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::SwapDisputed {}.to_string()
+            );
+
+            let err = app
+                .execute_contract(Addr::unchecked(USER1), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            app.execute_contract(
+                Addr::unchecked(&arbiter.address),
+                cosmoswap_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let res = app.wrap().query_balance(&arbiter.address, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(100));
+            let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(999_450));
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(450));
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(&arbiter.address),
+                    cosmoswap_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::SwapLocked {}.to_string()
+            );
+        }
+
+        #[test]
+        fn test_outcomes() {
+            let mut app = mock_app();
+            let arbiter = arbiter(&app);
+
+            let cosmoswap_addr = dispute_instantiate(&mut app, Some(arbiter.clone()));
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::RaiseDispute {},
+                &[],
+            )
+            .unwrap();
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(&arbiter.address),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Resolve {
+                        outcome: DisputeOutcome::Split {
+                            user1_share: Decimal::percent(150),
+                        },
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::InvalidOutcome {}.to_string()
+            );
+            app.execute_contract(
+                Addr::unchecked(&arbiter.address),
+                cosmoswap_addr,
+                &ExecuteMsg::Resolve {
+                    outcome: DisputeOutcome::User1,
+                },
+                &[],
+            )
+            .unwrap();
+            let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(999_900));
+
+            let cosmoswap_addr = dispute_instantiate(&mut app, Some(arbiter.clone()));
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::RaiseDispute {},
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(
+                Addr::unchecked(&arbiter.address),
+                cosmoswap_addr,
+                &ExecuteMsg::Resolve {
+                    outcome: DisputeOutcome::User2,
+                },
+                &[],
+            )
+            .unwrap();
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(900));
+            let res = app.wrap().query_balance(&arbiter.address, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(200));
+        }
+
+        #[test]
+        fn test_no_arbiter() {
+            let mut app = mock_app();
+            let cosmoswap_addr = dispute_instantiate(&mut app, None);
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_addr,
+                    &ExecuteMsg::RaiseDispute {},
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::NoArbiter {}.to_string()
+            );
+        }
+    }
+
+    mod confirmation {
+        use super::*;
+
+        fn confirmation_instantiate(app: &mut TestApp) -> Addr {
+            proper_instantiate(
+                app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: Some(100),
+                    pricing: None,
+                },
+                Expiration::Never {},
+            )
+        }
+
+        fn deposit(app: &mut TestApp, cosmoswap_addr: &Addr) {
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(5_000, DENOM2)],
+            )
+            .unwrap();
+        }
+
+        fn status(app: &TestApp, cosmoswap_addr: &Addr) -> SwapStatus {
+            app.wrap()
+                .query_wasm_smart(cosmoswap_addr, &QueryMsg::Status {})
+                .unwrap()
+        }
+
+        #[test]
+        fn test_confirm() {
+            let mut app = mock_app();
+            let cosmoswap_addr = confirmation_instantiate(&mut app);
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Confirm {},
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::NoDeposit {}.to_string()
+            );
+
+            // Accepting only escrows coin2
+            deposit(&mut app, &cosmoswap_addr);
+            assert_eq!(
+                status(&app, &cosmoswap_addr),
+                SwapStatus::Deposited {
+                    confirm_before: app.block_info().time.plus_seconds(100)
+                }
+            );
+            let res = app.wrap().query_balance(USER1, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::zero());
+            let res = app.wrap().query_balance(&cosmoswap_addr, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(5_000));
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Accept { recipient: None },
+                    &[coin(5_000, DENOM2)],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::SwapLocked {}.to_string()
+            );
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::WithdrawDeposit {},
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::ConfirmationPending {}.to_string()
+            );
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Confirm {},
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Unauthorized {}.to_string()
+            );
+
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::Confirm {},
+                &[],
+            )
+            .unwrap();
+            assert_eq!(status(&app, &cosmoswap_addr), SwapStatus::Completed);
+
+            let res = app.wrap().query_balance(USER1, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(4_750));
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(950));
+        }
+
+        #[test]
+        fn test_withdraw_deposit() {
+            let mut app = mock_app();
+            let cosmoswap_addr = confirmation_instantiate(&mut app);
+
+            deposit(&mut app, &cosmoswap_addr);
+            app.update_block(|block| block.time = block.time.plus_seconds(100));
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER1),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Confirm {},
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::ConfirmationExpired {}.to_string()
+            );
+
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::WithdrawDeposit {},
+                &[],
+            )
+            .unwrap();
+            assert_eq!(status(&app, &cosmoswap_addr), SwapStatus::Open);
+            let res = app.wrap().query_balance(USER2, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(1_000_000));
+
+            // Swap is open again and can be cancelled by user1
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::Cancel {},
+                &[],
+            )
+            .unwrap();
+            assert_eq!(status(&app, &cosmoswap_addr), SwapStatus::Cancelled);
+            let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(1_000_000));
+        }
+
+        #[test]
+        fn test_reject_deposit() {
+            let mut app = mock_app();
+            let cosmoswap_addr = confirmation_instantiate(&mut app);
+
+            deposit(&mut app, &cosmoswap_addr);
+
+            // Cancelling returns both coins
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::Cancel {},
+                &[],
+            )
+            .unwrap();
+            let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(1_000_000));
+            let res = app.wrap().query_balance(USER2, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(1_000_000));
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr,
+                    &ExecuteMsg::WithdrawDeposit {},
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::NoDeposit {}.to_string()
+            );
+        }
+    }
+
+    mod htlc {
+        use super::*;
+
+        const PREIMAGE: &[u8] = b"secret";
+
+        fn htlc_instantiate(app: &mut TestApp, expiration: Expiration) -> Addr {
+            instantiate_swap(
+                app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                SwapInfo {
+                    user1: Addr::unchecked(USER1).to_string(),
+                    user2: Addr::unchecked(USER2).to_string(),
+                    coin1: SwapCoin {
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                },
+                expiration,
+                Some(HexBinary::from(Sha256::digest(PREIMAGE).to_vec())),
+            )
+        }
+
+        #[test]
+        fn test_claim() {
+            let mut app = mock_app();
+            let expiration_time = app.block_info().time.plus_seconds(100);
+            let cosmoswap_addr = htlc_instantiate(&mut app, Expiration::AtTime(expiration_time));
+
+            // Regular settlement is not possible
+            let msg = ExecuteMsg::Accept { recipient: None };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &msg,
+                    &[coin(5_000, DENOM2)],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::InvalidSwapMode {}.to_string()
+            );
+            let msg = ExecuteMsg::Cancel {};
+            let err = app
+                .execute_contract(Addr::unchecked(USER1), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::InvalidSwapMode {}.to_string()
+            );
+
+            let msg = ExecuteMsg::Claim {
+                preimage: HexBinary::from(b"wrong".as_slice()),
+            };
+            let err = app
+                .execute_contract(Addr::unchecked(USER2), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::InvalidPreimage {}.to_string()
+            );
+
+            let msg = ExecuteMsg::Claim {
+                preimage: HexBinary::from(PREIMAGE),
+            };
+            let res = app
+                .execute_contract(Addr::unchecked(USER2), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap();
+            let attrs: Vec<_> = res.events.iter().flat_map(|e| &e.attributes).collect();
+            assert!(attrs
+                .iter()
+                .any(|attr| attr.key == "preimage"
+                    && attr.value == HexBinary::from(PREIMAGE).to_hex()));
+
+            let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(950));
+            let res = app.wrap().query_balance(ADMIN, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(50));
+
+            let err = app
+                .execute_contract(Addr::unchecked(USER2), cosmoswap_addr, &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::SwapLocked {}.to_string()
+            );
+        }
+
+        #[test]
+        fn test_refund() {
+            let mut app = mock_app();
+            let expiration_time = app.block_info().time.plus_seconds(100);
+            let cosmoswap_addr = htlc_instantiate(&mut app, Expiration::AtTime(expiration_time));
+
+            let msg = ExecuteMsg::Refund {};
+            let err = app
+                .execute_contract(Addr::unchecked(USER1), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::SwapNotExpired {}.to_string()
+            );
+
+            app.update_block(|block| block.time = block.time.plus_seconds(100));
+
+            // Preimage cannot be used after the timeout
+            let msg = ExecuteMsg::Claim {
+                preimage: HexBinary::from(PREIMAGE),
+            };
+            let err = app
+                .execute_contract(Addr::unchecked(USER2), cosmoswap_addr.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::SwapLocked {}.to_string()
+            );
+
+            let msg = ExecuteMsg::Refund {};
+            app.execute_contract(Addr::unchecked(USER1), cosmoswap_addr, &msg, &[])
+                .unwrap();
+            let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+            assert_eq!(res.amount, Uint128::new(1_000_000));
+        }
+    }
+    mod pricing {
+        use super::*;
+
+        fn auction_instantiate(app: &mut TestApp, coin2: SwapCoin, decay: PriceDecay) -> Addr {
+            let start_time = app.block_info().time;
+            proper_instantiate(
+                app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
+                    },
+                    coin2,
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: Some(DutchAuction {
+                        start_amount: Uint128::new(10_000),
+                        start_time,
+                        end_time: start_time.plus_seconds(100),
+                        decay,
+                    }),
+                },
+                Expiration::Never {},
+            )
+        }
+
+        #[test]
+        fn test_native_overpayment() {
+            let mut app = mock_app();
+            let cosmoswap_addr = auction_instantiate(
+                &mut app,
+                SwapCoin {
+                    is_native: true,
+                    coin: coin(5_000, DENOM2),
+                    cw20_address: None,
+                },
+                PriceDecay::Linear,
+            );
+
+            // Ask decays linearly from 10_000 to 5_000
+            app.update_block(|block| block.time = block.time.plus_seconds(50));
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER2),
+                    cosmoswap_addr.clone(),
+                    &ExecuteMsg::Accept { recipient: None },
+                    &[coin(7_000, DENOM2)],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                ContractError::Funds(FundsError::InvalidFunds {
+                    got: "7000".to_string(),
+                    expected: "7500".to_string(),
+                })
+                .to_string()
+            );
+
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cosmoswap_addr,
+                &ExecuteMsg::Accept { recipient: None },
+                &[coin(8_000, DENOM2)],
+            )
+            .unwrap();
+
+            let res = app.wrap().query_balance(USER2, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(992_500));
+            let res = app.wrap().query_balance(USER1, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(7_125));
+            let res = app.wrap().query_balance(ADMIN, DENOM2).unwrap();
+            assert_eq!(res.amount, Uint128::new(375));
+        }
+
+        #[test]
+        fn test_cw20_overpayment() {
+            let mut app = mock_app();
+            let cw20_addr = setup_cw20_token(&mut app, "teto");
+            let cosmoswap_addr = auction_instantiate(
+                &mut app,
+                SwapCoin {
+                    is_native: false,
+                    coin: coin(5_000, "teto"),
+                    cw20_address: Some(cw20_addr.to_string()),
+                },
+                PriceDecay::Linear,
+            );
+
+            // Ask is at the floor once the auction ends
+            app.update_block(|block| block.time = block.time.plus_seconds(200));
+            app.execute_contract(
+                Addr::unchecked(USER2),
+                cw20_addr.clone(),
+                &Cw20ExecuteMsg::Send {
+                    contract: cosmoswap_addr.to_string(),
+                    amount: Uint128::new(6_000),
+                    msg: to_json_binary(&ReceiveMsg::Accept { recipient: None }).unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+
+            assert_eq!(cw20_balance(&app, &cw20_addr, USER2), Uint128::new(995_000));
+            assert_eq!(
+                cw20_balance(&app, &cw20_addr, USER1),
+                Uint128::new(1_004_750)
+            );
+            assert_eq!(
+                cw20_balance(&app, &cw20_addr, cosmoswap_addr.as_str()),
+                Uint128::zero()
+            );
+        }
+    }
+}
+
+mod query {
+    use super::*;
+
+    mod simulate_accept {
+        use super::*;
+
+        #[test]
+        fn test_happy_path() {
+            let mut app = mock_app();
+            let expiration_height = app.block_info().height + 10;
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                SwapInfo {
+                    user1: Addr::unchecked(USER1).to_string(),
+                    user2: Addr::unchecked(USER2).to_string(),
+                    coin1: SwapCoin {
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: None,
+                },
+                Expiration::AtHeight(expiration_height),
+            );
+
+            let res: SimulateAcceptResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cosmoswap_addr.clone(),
+                    &QueryMsg::SimulateAccept {
+                        sender: USER2.to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(res.user1_receives, coin(4_750, DENOM2));
+            assert_eq!(res.user2_receives, coin(950, DENOM1));
+            assert_eq!(res.coin1_fee, coin(50, DENOM1));
+            assert_eq!(res.coin2_fee, coin(250, DENOM2));
+            assert!(!res.is_expired);
+            assert!(!res.is_locked);
+            assert!(res.is_authorized);
+            assert_eq!(res.reason, None);
+
+            let res: SimulateAcceptResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cosmoswap_addr.clone(),
+                    &QueryMsg::SimulateAccept {
+                        sender: USER1.to_string(),
+                    },
+                )
+                .unwrap();
+            assert!(!res.is_authorized);
+            assert_eq!(res.reason, Some(ContractError::Unauthorized {}.to_string()));
+
+            app.update_block(|block| block.height += 10);
+            let res: SimulateAcceptResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cosmoswap_addr.clone(),
+                    &QueryMsg::SimulateAccept {
+                        sender: USER2.to_string(),
+                    },
+                )
+                .unwrap();
+            assert!(res.is_expired);
+            assert!(!res.is_authorized);
+            assert_eq!(res.reason, Some(ContractError::SwapLocked {}.to_string()));
+
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::Cancel {},
+                &[],
+            )
+            .unwrap();
+            let res: SimulateAcceptResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cosmoswap_addr,
+                    &QueryMsg::SimulateAccept {
+                        sender: USER2.to_string(),
+                    },
+                )
+                .unwrap();
+            assert!(res.is_locked);
+            assert!(!res.is_authorized);
+        }
+
+        #[test]
+        fn test_not_acceptable() {
+            let mut app = mock_app();
+            let swap_info = SwapInfo {
+                user1: USER1.to_string(),
+                user2: USER2.to_string(),
+                coin1: SwapCoin {
+                    is_native: true,
+                    coin: coin(1_000, DENOM1),
+                    cw20_address: None,
+                },
+                coin2: SwapCoin {
+                    is_native: true,
+                    coin: coin(5_000, DENOM2),
+                    cw20_address: None,
+                },
+                user1_recipient: None,
+                arbiter: None,
+                confirmation_window: None,
+                pricing: None,
+            };
+            let fee_info = FeeInfo {
+                percentage: Decimal::from_str("0.05").unwrap(),
+                payment_address: Addr::unchecked(ADMIN),
+                rounding: Rounding::Floor,
+                bounds: vec![],
+                destination: FeeDestination::Transfer,
+            };
+            let simulate = |app: &TestApp, cosmoswap_addr: &Addr| -> SimulateAcceptResponse {
+                app.wrap()
+                    .query_wasm_smart(
+                        cosmoswap_addr,
+                        &QueryMsg::SimulateAccept {
+                            sender: USER2.to_string(),
+                        },
+                    )
+                    .unwrap()
+            };
+
+            // Hashed time-locked swaps are settled with claim
+            let expiration = Expiration::AtHeight(app.block_info().height + 10);
+            let cosmoswap_addr = instantiate_swap(
+                &mut app,
+                fee_info.clone(),
+                swap_info.clone(),
+                expiration,
+                Some(HexBinary::from(Sha256::digest(b"secret").to_vec())),
+            );
+            let res = simulate(&app, &cosmoswap_addr);
+            assert!(!res.is_authorized);
+            assert_eq!(
+                res.reason,
+                Some(ContractError::InvalidSwapMode {}.to_string())
+            );
+
+            // Cancelled swaps can not be accepted
+            let cosmoswap_addr =
+                proper_instantiate(&mut app, fee_info, swap_info, Expiration::Never {});
+            app.execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_addr.clone(),
+                &ExecuteMsg::Cancel {},
+                &[],
+            )
+            .unwrap();
+            let res = simulate(&app, &cosmoswap_addr);
+            assert!(res.is_locked);
+            assert!(!res.is_authorized);
+            assert_eq!(res.reason, Some(ContractError::SwapLocked {}.to_string()));
+        }
+    }
+
+    mod current_price {
+        use super::*;
+
+        #[test]
+        fn test_exponential_decay() {
+            let mut app = mock_app();
+            let start_time = app.block_info().time;
+            let cosmoswap_addr = proper_instantiate(
+                &mut app,
+                FeeInfo {
+                    percentage: Decimal::from_str("0.05").unwrap(),
+                    payment_address: Addr::unchecked(ADMIN),
+                    rounding: Rounding::Floor,
+                    bounds: vec![],
+                    destination: FeeDestination::Transfer,
+                },
+                SwapInfo {
+                    user1: USER1.to_string(),
+                    user2: USER2.to_string(),
+                    coin1: SwapCoin {
+                        is_native: true,
+                        coin: coin(1_000, DENOM1),
+                        cw20_address: None,
+                    },
+                    coin2: SwapCoin {
+                        is_native: true,
+                        coin: coin(5_000, DENOM2),
+                        cw20_address: None,
+                    },
+                    user1_recipient: None,
+                    arbiter: None,
+                    confirmation_window: None,
+                    pricing: Some(DutchAuction {
+                        start_amount: Uint128::new(10_000),
+                        start_time: start_time.plus_seconds(10),
+                        end_time: start_time.plus_seconds(110),
+                        decay: PriceDecay::Exponential {
+                            percentage: Decimal::percent(50),
+                            interval: 10,
+                        },
+                    }),
+                },
+                Expiration::Never {},
+            );
+            let current_price = |app: &TestApp| -> Coin {
+                app.wrap()
+                    .query_wasm_smart(&cosmoswap_addr, &QueryMsg::CurrentPrice {})
+                    .unwrap()
+            };
+
+            assert_eq!(current_price(&app), coin(10_000, DENOM2));
+
+            // Amount above the floor is halved every 10 seconds
+            app.update_block(|block| block.time = block.time.plus_seconds(35));
+            assert_eq!(current_price(&app), coin(6_250, DENOM2));
+            let res: SimulateAcceptResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    &cosmoswap_addr,
+                    &QueryMsg::SimulateAccept {
+                        sender: USER2.to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(res.user1_receives, coin(5_938, DENOM2));
+
+            app.update_block(|block| block.time = block.time.plus_seconds(100));
+            assert_eq!(current_price(&app), coin(5_000, DENOM2));
         }
     }
 }
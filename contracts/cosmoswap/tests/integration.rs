@@ -3,35 +3,46 @@ use cosmoswap::msg::ReceiveMsg;
 use cosmoswap::ContractError;
 use cosmoswap_controller::msg::{
     ExecuteMsg as ControllerExecuteMsg, InstantiateMsg as ControllerInstantiateMsg,
+    PredictSwapAddressResponse, QueryMsg as ControllerQueryMsg, ReceiveMsg as ControllerReceiveMsg,
 };
-use cosmoswap_packages::types::{SwapCoin, SwapInfo};
-use cosmwasm_std::to_binary;
+use cosmoswap_controller::state::Role;
+use cosmoswap_controller::ContractError as ControllerContractError;
+use cosmoswap_packages::funds::FundsError;
+use cosmoswap_packages::types::{FeeDestination, Recipient, Rounding, SwapCoin, SwapInfo};
+use cosmwasm_std::to_json_binary;
 use cosmwasm_std::{coin, Addr, Decimal, Empty, Uint128};
 use cw20::Cw20Coin;
 use cw20::Expiration;
 use cw20::{BalanceResponse, Cw20QueryMsg};
 use cw20_base::msg::{ExecuteMsg as Cw20ExecuteMsg, InstantiateMsg as Cw20InstantiateMsg};
-use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+use cw_multi_test::addons::{MockAddressGenerator, MockApiBech32};
+use cw_multi_test::{App, AppBuilder, BankKeeper, Contract, ContractWrapper, Executor, WasmKeeper};
 use std::str::FromStr;
 
-const ADMIN: &str = "juno..admin";
-const USER1: &str = "juno..user1";
-const USER2: &str = "juno..user2";
+const ADMIN: &str = "juno1335hded4gyzpt00fpz75mms4m7ck02wgw07yhw9grahj4dzg4yvq4sfful";
+const USER1: &str = "juno1pgzph9rze2j2xxavx4n7pdhxlkgsq7rak245x0vk7mgh3j4le6gq2ltl4u";
+const USER2: &str = "juno1vqjarrly327529599rcc4qhzvhwe34pp5uyy4gylvxe5zupeqx3se0zcpp";
 const DENOM1: &str = "denom1";
 const DENOM2: &str = "denom2";
 const CW20_TICKER: &str = "teto";
 
-fn mock_app() -> App {
-    AppBuilder::new().build(|router, _, storage| {
-        router
-            .bank
-            .init_balance(storage, &Addr::unchecked(USER1), vec![coin(2000, DENOM1)])
-            .unwrap();
-        router
-            .bank
-            .init_balance(storage, &Addr::unchecked(USER2), vec![coin(5000, DENOM2)])
-            .unwrap();
-    })
+type TestApp = App<BankKeeper, MockApiBech32>;
+
+// Bech32 addresses are needed for predicting instantiate2 addresses
+fn mock_app() -> TestApp {
+    AppBuilder::new()
+        .with_api(MockApiBech32::new("juno"))
+        .with_wasm(WasmKeeper::new().with_address_generator(MockAddressGenerator))
+        .build(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(USER1), vec![coin(2000, DENOM1)])
+                .unwrap();
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(USER2), vec![coin(5000, DENOM2)])
+                .unwrap();
+        })
 }
 
 fn cosmoswap() -> Box<dyn Contract<Empty>> {
@@ -62,25 +73,44 @@ fn cw20_contract() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
-fn proper_instantiate(app: &mut App, cosmoswap_code_id: u64, fee_percentage: &str) -> Addr {
+fn proper_instantiate(app: &mut TestApp, cosmoswap_code_id: u64, fee_percentage: &str) -> Addr {
     let cosmoswap_controller_code_id = app.store_code(cosmoswap_controller());
     let msg = ControllerInstantiateMsg {
         cosmoswap_code_id,
+        cosmoswap_version: "0.1.0".to_string(),
         fee_percentage: Decimal::from_str(fee_percentage).unwrap(),
         fee_payment_address: Addr::unchecked(ADMIN).to_string(),
+        fee_rounding: Rounding::Floor,
+        fee_bounds: vec![],
+        fee_destination: FeeDestination::Transfer,
     };
     app.instantiate_contract(
         cosmoswap_controller_code_id,
         Addr::unchecked(ADMIN),
         &msg,
-        &vec![],
+        &[],
         "cosmoswap-controller",
         None,
     )
     .unwrap()
 }
 
-fn setup_cw20_token(app: &mut App) -> Addr {
+fn predict_swap_address(app: &TestApp, cosmoswap_controller_addr: &Addr, nonce: u64) -> Addr {
+    let res: PredictSwapAddressResponse = app
+        .wrap()
+        .query_wasm_smart(
+            cosmoswap_controller_addr,
+            &ControllerQueryMsg::PredictSwapAddress {
+                maker: USER1.to_string(),
+                nonce: Some(nonce),
+                code_id: None,
+            },
+        )
+        .unwrap();
+    res.address
+}
+
+fn setup_cw20_token(app: &mut TestApp) -> Addr {
     let cw20_code_id = app.store_code(cw20_contract());
 
     // Create a new cw20 token
@@ -99,7 +129,7 @@ fn setup_cw20_token(app: &mut App) -> Addr {
         cw20_code_id,
         Addr::unchecked(ADMIN),
         &msg,
-        &vec![],
+        &[],
         "test cw20",
         None,
     )
@@ -129,37 +159,44 @@ mod native_token {
                 coin: coin(5_000, DENOM2),
                 cw20_address: None,
             },
+            user1_recipient: None,
+            arbiter: None,
+            confirmation_window: None,
+            pricing: None,
         };
-        // Contract1
+        let swap1_addr = predict_swap_address(&app, &cosmoswap_controller_addr, 0);
+        let swap2_addr = predict_swap_address(&app, &cosmoswap_controller_addr, 1);
         app.execute_contract(
             Addr::unchecked(USER1),
             cosmoswap_controller_addr.clone(),
             &ControllerExecuteMsg::CreateSwap {
                 swap_info: swap_info.clone(),
                 expiration: Expiration::Never {},
+                code_id: None,
+                hash_lock: None,
             },
-            &vec![swap_info.clone().coin1.coin],
+            &[swap_info.clone().coin1.coin],
         )
         .unwrap();
-        // Contract2
         app.execute_contract(
             Addr::unchecked(USER1),
             cosmoswap_controller_addr.clone(),
             &ControllerExecuteMsg::CreateSwap {
                 swap_info: swap_info.clone(),
                 expiration: Expiration::Never {},
+                code_id: None,
+                hash_lock: None,
             },
-            &vec![swap_info.clone().coin1.coin],
+            &[swap_info.clone().coin1.coin],
         )
         .unwrap();
 
-        // cosmoswap address is contract1
-        let msg = ExecuteMsg::Accept {};
+        let msg = ExecuteMsg::Accept { recipient: None };
         app.execute_contract(
             Addr::unchecked(USER2),
-            Addr::unchecked("contract1"),
+            swap1_addr.clone(),
             &msg,
-            &vec![swap_info.clone().coin2.coin],
+            &[swap_info.clone().coin2.coin],
         )
         .unwrap();
 
@@ -181,14 +218,9 @@ mod native_token {
         let res = app.wrap().query_balance(ADMIN, DENOM2).unwrap();
         assert_eq!(res.amount, Uint128::new(250));
 
-        let msg = ExecuteMsg::Accept {};
+        let msg = ExecuteMsg::Accept { recipient: None };
         let err = app
-            .execute_contract(
-                Addr::unchecked(USER2),
-                Addr::unchecked("contract1"),
-                &msg,
-                &vec![],
-            )
+            .execute_contract(Addr::unchecked(USER2), swap1_addr.clone(), &msg, &[])
             .unwrap_err();
         assert_eq!(
             err.source().unwrap().to_string(),
@@ -197,22 +229,12 @@ mod native_token {
 
         // Cancel the second swap
         let msg = ExecuteMsg::Cancel {};
-        app.execute_contract(
-            Addr::unchecked(USER1),
-            Addr::unchecked("contract2"),
-            &msg,
-            &vec![],
-        )
-        .unwrap();
+        app.execute_contract(Addr::unchecked(USER1), swap2_addr.clone(), &msg, &[])
+            .unwrap();
 
-        let msg = ExecuteMsg::Accept {};
+        let msg = ExecuteMsg::Accept { recipient: None };
         let err = app
-            .execute_contract(
-                Addr::unchecked(USER2),
-                Addr::unchecked("contract2"),
-                &msg,
-                &vec![],
-            )
+            .execute_contract(Addr::unchecked(USER2), swap2_addr.clone(), &msg, &[])
             .unwrap_err();
         assert_eq!(
             err.source().unwrap().to_string(),
@@ -246,40 +268,47 @@ mod cw20_token {
                 coin: coin(5_000, CW20_TICKER),
                 cw20_address: Some(cw20_addr.to_string()),
             },
+            user1_recipient: None,
+            arbiter: None,
+            confirmation_window: None,
+            pricing: None,
         };
-        // Contract2
+        let swap1_addr = predict_swap_address(&app, &cosmoswap_controller_addr, 0);
+        let swap2_addr = predict_swap_address(&app, &cosmoswap_controller_addr, 1);
         app.execute_contract(
             Addr::unchecked(USER1),
             cosmoswap_controller_addr.clone(),
             &ControllerExecuteMsg::CreateSwap {
                 swap_info: swap_info.clone(),
                 expiration: Expiration::Never {},
+                code_id: None,
+                hash_lock: None,
             },
-            &vec![swap_info.clone().coin1.coin],
+            &[swap_info.clone().coin1.coin],
         )
         .unwrap();
-        // Contract3
         app.execute_contract(
             Addr::unchecked(USER1),
             cosmoswap_controller_addr.clone(),
             &ControllerExecuteMsg::CreateSwap {
                 swap_info: swap_info.clone(),
                 expiration: Expiration::Never {},
+                code_id: None,
+                hash_lock: None,
             },
-            &vec![swap_info.clone().coin1.coin],
+            &[swap_info.clone().coin1.coin],
         )
         .unwrap();
 
-        // cosmoswap address is contract1
         app.execute_contract(
             Addr::unchecked(USER2),
             cw20_addr.clone(),
             &Cw20ExecuteMsg::Send {
-                contract: "contract2".to_string(),
+                contract: swap1_addr.to_string(),
                 amount: Uint128::new(5_000),
-                msg: to_binary(&ReceiveMsg::Accept {}).unwrap(),
+                msg: to_json_binary(&ReceiveMsg::Accept { recipient: None }).unwrap(),
             },
-            &vec![],
+            &[],
         )
         .unwrap();
 
@@ -327,29 +356,386 @@ mod cw20_token {
 
         // Cancel the second swap
         let msg = ExecuteMsg::Cancel {};
+        app.execute_contract(Addr::unchecked(USER1), swap2_addr.clone(), &msg, &[])
+            .unwrap();
+
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                cw20_addr.clone(),
+                &Cw20ExecuteMsg::Send {
+                    contract: swap2_addr.to_string(),
+                    amount: Uint128::new(5_000),
+                    msg: to_json_binary(&ReceiveMsg::Accept { recipient: None }).unwrap(),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().source().unwrap().to_string(),
+            ContractError::SwapLocked {}.to_string()
+        )
+    }
+
+    #[test]
+    fn test_allowance() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+        let cw20_addr = setup_cw20_token(&mut app);
+
+        let swap_info = SwapInfo {
+            user1: USER1.to_string(),
+            user2: USER2.to_string(),
+            coin1: SwapCoin {
+                is_native: true,
+                coin: coin(1_000, DENOM1),
+                cw20_address: None,
+            },
+            coin2: SwapCoin {
+                is_native: false,
+                coin: coin(5_000, CW20_TICKER),
+                cw20_address: Some(cw20_addr.to_string()),
+            },
+            user1_recipient: None,
+            arbiter: None,
+            confirmation_window: None,
+            pricing: None,
+        };
+        let swap_addr = predict_swap_address(&app, &cosmoswap_controller_addr, 0);
         app.execute_contract(
             Addr::unchecked(USER1),
-            Addr::unchecked("contract3"),
-            &msg,
-            &vec![],
+            cosmoswap_controller_addr.clone(),
+            &ControllerExecuteMsg::CreateSwap {
+                swap_info: swap_info.clone(),
+                expiration: Expiration::Never {},
+                code_id: None,
+                hash_lock: None,
+            },
+            &[swap_info.clone().coin1.coin],
+        )
+        .unwrap();
+
+        // Accepting without an allowance fails up front
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                swap_addr.clone(),
+                &ExecuteMsg::Accept { recipient: None },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::Funds(FundsError::InsufficientAllowance {
+                got: "0".to_string(),
+                expected: "5000".to_string(),
+            })
+            .to_string()
+        );
+
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            cw20_addr.clone(),
+            &Cw20ExecuteMsg::IncreaseAllowance {
+                spender: swap_addr.to_string(),
+                amount: Uint128::new(5_000),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            swap_addr.clone(),
+            &ExecuteMsg::Accept { recipient: None },
+            &[],
+        )
+        .unwrap();
+
+        let res: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: USER1.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.balance, Uint128::new(4750));
+        let res: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: USER2.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.balance, Uint128::new(5_000));
+        let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(950));
+    }
+
+    #[test]
+    fn test_accept_through_controller() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+        let cw20_addr = setup_cw20_token(&mut app);
+
+        let swap_info = SwapInfo {
+            user1: USER1.to_string(),
+            user2: USER2.to_string(),
+            coin1: SwapCoin {
+                is_native: true,
+                coin: coin(1_000, DENOM1),
+                cw20_address: None,
+            },
+            coin2: SwapCoin {
+                is_native: false,
+                coin: coin(5_000, CW20_TICKER),
+                cw20_address: Some(cw20_addr.to_string()),
+            },
+            user1_recipient: None,
+            arbiter: None,
+            confirmation_window: None,
+            pricing: None,
+        };
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr.clone(),
+            &ControllerExecuteMsg::CreateSwap {
+                swap_info: swap_info.clone(),
+                expiration: Expiration::Never {},
+                code_id: None,
+                hash_lock: None,
+            },
+            &[swap_info.clone().coin1.coin],
         )
         .unwrap();
 
+        // Unknown swaps are rejected by the controller
         let err = app
             .execute_contract(
                 Addr::unchecked(USER2),
                 cw20_addr.clone(),
                 &Cw20ExecuteMsg::Send {
-                    contract: "contract3".to_string(),
+                    contract: cosmoswap_controller_addr.to_string(),
                     amount: Uint128::new(5_000),
-                    msg: to_binary(&ReceiveMsg::Accept {}).unwrap(),
+                    msg: to_json_binary(&ControllerReceiveMsg::AcceptSwap {
+                        swap_id: 2,
+                        recipient: None,
+                    })
+                    .unwrap(),
                 },
-                &vec![],
+                &[],
             )
             .unwrap_err();
         assert_eq!(
             err.source().unwrap().source().unwrap().to_string(),
-            ContractError::SwapLocked {}.to_string()
+            ControllerContractError::SwapNotFound {}.to_string()
+        );
+
+        // Only the controller can accept for the taker
+        let swap_addr = predict_swap_address(&app, &cosmoswap_controller_addr, 0);
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                cw20_addr.clone(),
+                &Cw20ExecuteMsg::Send {
+                    contract: swap_addr.to_string(),
+                    amount: Uint128::new(5_000),
+                    msg: to_json_binary(&ReceiveMsg::AcceptFor {
+                        taker: USER2.to_string(),
+                        recipient: None,
+                    })
+                    .unwrap(),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().source().unwrap().to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+
+        // Taker picks the recipient of its proceeds
+        let recipient = app.api().addr_make("recipient");
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            cw20_addr.clone(),
+            &Cw20ExecuteMsg::Send {
+                contract: cosmoswap_controller_addr.to_string(),
+                amount: Uint128::new(5_000),
+                msg: to_json_binary(&ControllerReceiveMsg::AcceptSwap {
+                    swap_id: 1,
+                    recipient: Some(Recipient::Address {
+                        address: recipient.to_string(),
+                    }),
+                })
+                .unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: USER1.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.balance, Uint128::new(4750));
+        let res: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: cosmoswap_controller_addr.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.balance, Uint128::zero());
+        let res = app.wrap().query_balance(&recipient, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(950));
+        let res = app.wrap().query_balance(USER2, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::zero());
+    }
+}
+
+mod pause {
+    use super::*;
+
+    #[test]
+    fn test_happy_path() {
+        let mut app = mock_app();
+        let cosmoswap_code_id = app.store_code(cosmoswap());
+        let cosmoswap_controller_addr = proper_instantiate(&mut app, cosmoswap_code_id, "0.05");
+
+        let swap_info = SwapInfo {
+            user1: USER1.to_string(),
+            user2: USER2.to_string(),
+            coin1: SwapCoin {
+                is_native: true,
+                coin: coin(1_000, DENOM1),
+                cw20_address: None,
+            },
+            coin2: SwapCoin {
+                is_native: true,
+                coin: coin(5_000, DENOM2),
+                cw20_address: None,
+            },
+            user1_recipient: None,
+            arbiter: None,
+            confirmation_window: None,
+            pricing: None,
+        };
+        let swap_addr = predict_swap_address(&app, &cosmoswap_controller_addr, 0);
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr.clone(),
+            &ControllerExecuteMsg::CreateSwap {
+                swap_info: swap_info.clone(),
+                expiration: Expiration::Never {},
+                code_id: None,
+                hash_lock: None,
+            },
+            &[swap_info.clone().coin1.coin],
         )
+        .unwrap();
+
+        // Pauser pauses both creation and settlement
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cosmoswap_controller_addr.clone(),
+            &ControllerExecuteMsg::GrantRole {
+                role: Role::Pauser,
+                address: ADMIN.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cosmoswap_controller_addr.clone(),
+            &ControllerExecuteMsg::SetPause {
+                creation: true,
+                settlement: true,
+            },
+            &[],
+        )
+        .unwrap();
+
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER1),
+                cosmoswap_controller_addr.clone(),
+                &ControllerExecuteMsg::CreateSwap {
+                    swap_info: swap_info.clone(),
+                    expiration: Expiration::Never {},
+                    code_id: None,
+                    hash_lock: None,
+                },
+                &[swap_info.clone().coin1.coin],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ControllerContractError::CreationPaused {}.to_string()
+        );
+
+        let err = app
+            .execute_contract(
+                Addr::unchecked(USER2),
+                swap_addr.clone(),
+                &ExecuteMsg::Accept { recipient: None },
+                &[swap_info.clone().coin2.coin],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            ContractError::SettlementPaused {}.to_string()
+        );
+
+        // Makers can always get their funds back
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            swap_addr.clone(),
+            &ExecuteMsg::Cancel {},
+            &[],
+        )
+        .unwrap();
+        let res = app.wrap().query_balance(USER1, DENOM1).unwrap();
+        assert_eq!(res.amount, Uint128::new(2_000));
+
+        // Unpausing allows creating swaps again
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cosmoswap_controller_addr.clone(),
+            &ControllerExecuteMsg::SetPause {
+                creation: false,
+                settlement: false,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER1),
+            cosmoswap_controller_addr,
+            &ControllerExecuteMsg::CreateSwap {
+                swap_info: swap_info.clone(),
+                expiration: Expiration::Never {},
+                code_id: None,
+                hash_lock: None,
+            },
+            &[swap_info.coin1.coin],
+        )
+        .unwrap();
     }
 }
@@ -0,0 +1,136 @@
+use crate::types::{FeeBound, FeeDestination, FeeInfo, Rounding, SwapCoin};
+use cosmwasm_std::{
+    coin, to_json_binary, BankMsg, CosmosMsg, Decimal, StdError, StdResult, Uint128, Uint256,
+    WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use std::convert::TryFrom;
+use thiserror::Error;
+
+/// Calculates the fee for the given swap coin using the rounding mode and bounds in the fee info
+pub fn calculate_fee(fee_info: &FeeInfo, swap_coin: &SwapCoin) -> Result<Uint128, FeeError> {
+    let amount = swap_coin.coin.amount;
+    let mut fee = apply_percentage(amount, fee_info.percentage, &fee_info.rounding)?;
+
+    let asset = swap_coin.asset();
+    if let Some(bound) = fee_info.bounds.iter().find(|bound| bound.asset == asset) {
+        if let Some(min_fee) = bound.min_fee {
+            fee = fee.max(min_fee);
+        }
+        if let Some(max_fee) = bound.max_fee {
+            fee = fee.min(max_fee);
+        }
+    }
+
+    // Fee needs to leave something for the receiver of the swapped amount
+    if !fee.is_zero() && fee >= amount {
+        return Err(FeeError::SwapTooSmall {
+            amount: amount.to_string(),
+            min_fee: fee.to_string(),
+        });
+    }
+    Ok(fee)
+}
+
+/// Sends the fee to the fee destination
+pub fn fee_msg(fee_config: &FeeInfo, swap_coin: &SwapCoin, fee: Uint128) -> StdResult<CosmosMsg> {
+    let payment_address = fee_config.payment_address.to_string();
+    let msg = match (&fee_config.destination, swap_coin.is_native) {
+        (FeeDestination::Transfer, true) => CosmosMsg::Bank(BankMsg::Send {
+            to_address: payment_address,
+            amount: vec![coin(fee.u128(), swap_coin.coin.denom.clone())],
+        }),
+        (FeeDestination::Transfer, false) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: swap_coin.cw20_address.as_ref().unwrap().to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: payment_address,
+                amount: fee,
+            })?,
+            funds: vec![],
+        }),
+        (FeeDestination::Burn, true) => CosmosMsg::Bank(BankMsg::Burn {
+            amount: vec![coin(fee.u128(), swap_coin.coin.denom.clone())],
+        }),
+        (FeeDestination::Burn, false) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: swap_coin.cw20_address.as_ref().unwrap().to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Burn { amount: fee })?,
+            funds: vec![],
+        }),
+        (FeeDestination::Hook { msg }, true) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: payment_address,
+            msg: msg.clone(),
+            funds: vec![coin(fee.u128(), swap_coin.coin.denom.clone())],
+        }),
+        (FeeDestination::Hook { msg }, false) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: swap_coin.cw20_address.as_ref().unwrap().to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                contract: payment_address,
+                amount: fee,
+                msg: msg.clone(),
+            })?,
+            funds: vec![],
+        }),
+    };
+    Ok(msg)
+}
+
+/// Multiplies the amount with the percentage and rounds the result
+pub fn apply_percentage(
+    amount: Uint128,
+    percentage: Decimal,
+    rounding: &Rounding,
+) -> Result<Uint128, FeeError> {
+    let numerator = Uint256::from(amount) * Uint256::from(percentage.atomics());
+    let denominator = Uint256::from(Decimal::one().atomics());
+
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+
+    let round_up = match rounding {
+        Rounding::Floor => false,
+        Rounding::Ceil => !remainder.is_zero(),
+        Rounding::Bankers => {
+            let double_remainder = remainder * Uint256::from(2u8);
+            double_remainder > denominator
+                || (double_remainder == denominator
+                    && quotient % Uint256::from(2u8) == Uint256::one())
+        }
+    };
+
+    let result = match round_up {
+        true => quotient + Uint256::one(),
+        false => quotient,
+    };
+    Ok(Uint128::try_from(result).map_err(StdError::from)?)
+}
+
+/// Makes sure every asset has a single bound and minimum fees are not higher than maximum fees
+pub fn validate_fee_bounds(bounds: &[FeeBound]) -> Result<(), FeeError> {
+    for (i, bound) in bounds.iter().enumerate() {
+        if bounds[..i].iter().any(|b| b.asset == bound.asset) {
+            return Err(FeeError::InvalidFeeBounds {
+                asset: bound.asset.to_string(),
+            });
+        }
+        if let (Some(min_fee), Some(max_fee)) = (bound.min_fee, bound.max_fee) {
+            if min_fee > max_fee {
+                return Err(FeeError::InvalidFeeBounds {
+                    asset: bound.asset.to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum FeeError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Swap amount {amount} is too small to cover the fee of {min_fee}")]
+    SwapTooSmall { amount: String, min_fee: String },
+
+    #[error("Invalid fee bounds for {asset}")]
+    InvalidFeeBounds { asset: String },
+}
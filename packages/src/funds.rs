@@ -1,11 +1,16 @@
-use cosmwasm_std::{Coin, MessageInfo, StdError};
+use crate::types::SwapCoin;
+use cosmwasm_std::{
+    coin, to_json_binary, Addr, BankMsg, BlockInfo, Coin, CosmosMsg, MessageInfo, QuerierWrapper,
+    StdError, StdResult, Uint128, WasmMsg,
+};
+use cw20::{AllowanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
 use thiserror::Error;
 
 pub fn check_single_coin(info: &MessageInfo, expected: &Coin) -> Result<(), FundsError> {
     if info.funds.len() != 1 {
         return Err(FundsError::MissingFunds {});
     };
-    let sent_fund = info.funds.get(0).unwrap();
+    let sent_fund = info.funds.first().unwrap();
     if sent_fund.denom != expected.denom {
         return Err(FundsError::InvalidDenom {
             got: sent_fund.denom.to_string(),
@@ -21,6 +26,97 @@ pub fn check_single_coin(info: &MessageInfo, expected: &Coin) -> Result<(), Fund
     Ok(())
 }
 
+/// Checks that at least the expected coin is sent and returns the amount sent on top of it
+pub fn check_min_coin(info: &MessageInfo, expected: &Coin) -> Result<Uint128, FundsError> {
+    if info.funds.len() != 1 {
+        return Err(FundsError::MissingFunds {});
+    };
+    let sent_fund = info.funds.first().unwrap();
+    if sent_fund.denom != expected.denom {
+        return Err(FundsError::InvalidDenom {
+            got: sent_fund.denom.to_string(),
+            expected: expected.denom.to_string(),
+        });
+    }
+    sent_fund
+        .amount
+        .checked_sub(expected.amount)
+        .map_err(|_| FundsError::InvalidFunds {
+            got: sent_fund.amount.to_string(),
+            expected: expected.amount.to_string(),
+        })
+}
+
+/// Sends the amount of the swap coin from the contract to the recipient
+pub fn transfer_msg(
+    swap_coin: &SwapCoin,
+    recipient: &Addr,
+    amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    let msg: CosmosMsg = if swap_coin.is_native {
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![coin(amount.u128(), swap_coin.coin.denom.clone())],
+        })
+    } else {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: swap_coin.cw20_address.as_ref().unwrap().to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        })
+    };
+    Ok(msg)
+}
+
+/// Checks the cw20 allowance up front so a missing approval fails with a clear error
+pub fn check_cw20_allowance(
+    querier: &QuerierWrapper,
+    block: &BlockInfo,
+    swap_coin: &SwapCoin,
+    owner: &Addr,
+    spender: &Addr,
+) -> Result<(), FundsError> {
+    let res: AllowanceResponse = querier.query_wasm_smart(
+        swap_coin.asset(),
+        &Cw20QueryMsg::Allowance {
+            owner: owner.to_string(),
+            spender: spender.to_string(),
+        },
+    )?;
+    let allowance = if res.expires.is_expired(block) {
+        Uint128::zero()
+    } else {
+        res.allowance
+    };
+    if allowance < swap_coin.coin.amount {
+        return Err(FundsError::InsufficientAllowance {
+            got: allowance.to_string(),
+            expected: swap_coin.coin.amount.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Pulls the cw20 swap coin from the owner with the allowance given to the contract
+pub fn transfer_from_msg(
+    swap_coin: &SwapCoin,
+    owner: &Addr,
+    recipient: &Addr,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: swap_coin.asset(),
+        msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+            owner: owner.to_string(),
+            recipient: recipient.to_string(),
+            amount: swap_coin.coin.amount,
+        })?,
+        funds: vec![],
+    }))
+}
+
 #[derive(Error, Debug, PartialEq)]
 pub enum FundsError {
     #[error("{0}")]
@@ -34,4 +130,7 @@ pub enum FundsError {
 
     #[error("No funds found!")]
     MissingFunds {},
+
+    #[error("Insufficient allowance! Got: {got} - Expected: {expected}")]
+    InsufficientAllowance { got: String, expected: String },
 }
@@ -1,2 +1,3 @@
+pub mod fees;
 pub mod funds;
 pub mod types;
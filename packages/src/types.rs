@@ -1,10 +1,39 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin, Decimal};
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, StdResult, Timestamp, Uint128};
 
 #[cw_serde]
 pub struct FeeInfo {
     pub percentage: Decimal,
     pub payment_address: Addr,
+    pub rounding: Rounding,
+    pub bounds: Vec<FeeBound>,
+    pub destination: FeeDestination,
+}
+
+#[cw_serde]
+pub enum FeeDestination {
+    // Fees are transferred to the payment address
+    Transfer,
+    // Fees are burned
+    Burn,
+    // Fees are sent to the payment address contract along with the hook message
+    Hook { msg: Binary },
+}
+
+#[cw_serde]
+pub enum Rounding {
+    Floor,
+    Ceil,
+    // Rounds half to even
+    Bankers,
+}
+
+#[cw_serde]
+pub struct FeeBound {
+    // Native denom or cw20 contract address
+    pub asset: String,
+    pub min_fee: Option<Uint128>,
+    pub max_fee: Option<Uint128>,
 }
 
 #[cw_serde]
@@ -13,6 +42,85 @@ pub struct SwapInfo {
     pub user2: String,
     pub coin1: SwapCoin,
     pub coin2: SwapCoin,
+    // Receives the coin2 proceeds of user1 instead of user1, user2 picks its own when accepting
+    pub user1_recipient: Option<Recipient>,
+    // Resolves disputes raised by either user before settlement
+    pub arbiter: Option<Arbiter>,
+    // Seconds user1 has to confirm after user2 deposits coin2, settles on accept if not set
+    pub confirmation_window: Option<u64>,
+    // Ask of coin2 decays to the coin2 amount when set
+    pub pricing: Option<DutchAuction>,
+}
+
+#[cw_serde]
+pub struct DutchAuction {
+    // Amount of coin2 asked until the start time
+    pub start_amount: Uint128,
+    pub start_time: Timestamp,
+    // The ask reaches the coin2 amount at the end time
+    pub end_time: Timestamp,
+    pub decay: PriceDecay,
+}
+
+#[cw_serde]
+pub enum PriceDecay {
+    Linear,
+    // Amount above the floor is cut by the percentage every interval in seconds
+    Exponential { percentage: Decimal, interval: u64 },
+}
+
+impl DutchAuction {
+    /// Returns the amount asked at the given time, decaying from the start amount to the floor
+    pub fn current_amount(&self, floor: Uint128, time: Timestamp) -> StdResult<Uint128> {
+        if time <= self.start_time {
+            return Ok(self.start_amount);
+        }
+        if time >= self.end_time {
+            return Ok(floor);
+        }
+
+        let premium = self.start_amount.checked_sub(floor)?;
+        let elapsed = time.seconds() - self.start_time.seconds();
+        let duration = self.end_time.seconds() - self.start_time.seconds();
+        let remaining = match &self.decay {
+            PriceDecay::Linear => premium.multiply_ratio(duration - elapsed, duration),
+            PriceDecay::Exponential {
+                percentage,
+                interval,
+            } => {
+                let steps = (elapsed / interval).min(u32::MAX as u64) as u32;
+                let factor = (Decimal::one() - percentage).checked_pow(steps)?;
+                premium.mul_floor(factor)
+            }
+        };
+        Ok(floor.checked_add(remaining)?)
+    }
+}
+
+#[cw_serde]
+pub struct Arbiter {
+    pub address: String,
+    // Share of coin1 paid to the arbiter when a dispute is resolved
+    pub fee_percentage: Decimal,
+}
+
+#[cw_serde]
+pub enum Recipient {
+    // Proceeds are sent to another address
+    Address { address: String },
+    // Proceeds are sent to a contract along with the hook message
+    Contract { address: String, msg: Binary },
+    // Proceeds are sent over IBC, only for native coins
+    Ibc(IbcRecipient),
+}
+
+#[cw_serde]
+pub struct IbcRecipient {
+    pub channel_id: String,
+    // Address on the counterparty chain
+    pub receiver: String,
+    // Seconds after settlement before the transfer times out
+    pub timeout: u64,
 }
 
 #[cw_serde]
@@ -21,3 +129,33 @@ pub struct SwapCoin {
     pub coin: Coin,
     pub cw20_address: Option<String>,
 }
+
+impl SwapCoin {
+    /// Returns the identifier used for fee bounds, the denom for native coins
+    /// and the contract address for cw20 tokens
+    pub fn asset(&self) -> String {
+        match (self.is_native, &self.cw20_address) {
+            (false, Some(cw20_address)) => cw20_address.to_string(),
+            _ => self.coin.denom.to_string(),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct PauseInfo {
+    pub creation: bool,
+    pub settlement: bool,
+}
+
+// Lifecycle events swap contracts report to the controller
+#[cw_serde]
+pub enum SwapEvent {
+    Completed,
+    Cancelled,
+    Refunded,
+    Resolved,
+    // user2 deposited coin2 on a two-phase swap
+    Deposited,
+    // user2 withdrew coin2 after the confirmation window
+    Withdrawn,
+}